@@ -0,0 +1,23 @@
+-- Desktop only: the web app's sql.js build has no FTS5
+-- Full-text index over draft content, stored externally in `draft`
+CREATE VIRTUAL TABLE `draft_fts` USING fts5(
+	`content`,
+	content='draft',
+	content_rowid='id',
+	tokenize='unicode61 remove_diacritics 2'
+);
+--> statement-breakpoint
+INSERT INTO `draft_fts` (`draft_fts`) VALUES ('rebuild');
+--> statement-breakpoint
+CREATE TRIGGER `draft_fts_insert` AFTER INSERT ON `draft` BEGIN
+	INSERT INTO `draft_fts` (`rowid`, `content`) VALUES (new.`id`, new.`content`);
+END;
+--> statement-breakpoint
+CREATE TRIGGER `draft_fts_delete` AFTER DELETE ON `draft` BEGIN
+	INSERT INTO `draft_fts` (`draft_fts`, `rowid`, `content`) VALUES ('delete', old.`id`, old.`content`);
+END;
+--> statement-breakpoint
+CREATE TRIGGER `draft_fts_update` AFTER UPDATE OF `content` ON `draft` BEGIN
+	INSERT INTO `draft_fts` (`draft_fts`, `rowid`, `content`) VALUES ('delete', old.`id`, old.`content`);
+	INSERT INTO `draft_fts` (`rowid`, `content`) VALUES (new.`id`, new.`content`);
+END;
@@ -0,0 +1,94 @@
+//! Running in the background.
+//!
+//! `dashtext --background` starts with the main window hidden, so only the
+//! hotkeys and tray are active. Closing the main window hides it instead of
+//! quitting whenever there's a way to bring it back, and an XDG autostart
+//! entry can start the app this way on login.
+
+use crate::{desktop_entry, windows};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Manager, Window, WindowEvent};
+
+/// Whether the app keeps running when the main window is closed
+pub struct BackgroundState {
+    launched_hidden: AtomicBool,
+    hide_on_close: AtomicBool,
+}
+
+impl BackgroundState {
+    pub fn new(background: bool) -> Self {
+        Self {
+            launched_hidden: AtomicBool::new(background),
+            hide_on_close: AtomicBool::new(background),
+        }
+    }
+
+    /// Whether the main window should stay hidden on first load
+    ///
+    /// Only the first call can return true, so reloads and later launches show it.
+    pub fn take_launched_hidden(&self) -> bool {
+        self.launched_hidden.swap(false, Ordering::Relaxed)
+    }
+
+    /// Hide the main window on close instead of quitting
+    pub fn set_hide_on_close(&self, enabled: bool) {
+        self.hide_on_close.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Handle window events, hiding the main window on close if enabled
+    ///
+    /// Otherwise closing the main window quits, with the window kept open
+    /// until the shutdown sequence has saved its content.
+    pub fn on_window_event(&self, window: &Window, event: &WindowEvent) {
+        let WindowEvent::CloseRequested { api, .. } = event else {
+            return;
+        };
+        if window.label() != windows::MAIN {
+            return;
+        }
+
+        api.prevent_close();
+        if !self.hide_on_close.load(Ordering::Relaxed) {
+            window.app_handle().exit(0);
+        } else if let Err(e) = window.hide() {
+            tracing::warn!("Failed to hide main window: {}", e);
+        }
+    }
+}
+
+/// Path of the autostart entry, `~/.config/autostart/dashtext.desktop`
+fn autostart_path() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| dir.join("autostart").join(desktop_entry::FILE_NAME))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+/// Whether the app starts in the background on login
+pub fn is_autostart_enabled() -> bool {
+    autostart_path().is_ok_and(|path| path.exists())
+}
+
+/// Install or remove the autostart entry
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    let path = autostart_path()?;
+
+    if enabled {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        let entry = desktop_entry::render_autostart()?;
+        fs::write(&path, entry).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        tracing::info!("Installed autostart entry {:?}", path);
+    } else {
+        match fs::remove_file(&path) {
+            Ok(()) => tracing::info!("Removed autostart entry {:?}", path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to remove {:?}: {}", path, e)),
+        }
+    }
+
+    Ok(())
+}
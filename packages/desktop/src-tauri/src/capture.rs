@@ -0,0 +1,117 @@
+//! Silent capture: append the current selection to a draft without showing a window.
+
+use crate::notify::{self, Action, Category, Notification};
+use crate::{conf::SettingsState, db, drafts};
+use sqlx::SqlitePool;
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+/// Commands that print the primary selection, tried in order
+const SELECTION_COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--primary", "--no-newline"]),
+    ("xclip", &["-o", "-selection", "primary"]),
+    ("xsel", &["--output", "--primary"]),
+];
+
+/// Commands that print the clipboard, used when the selection is empty
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-o", "-selection", "clipboard"]),
+    ("xsel", &["--output", "--clipboard"]),
+];
+
+/// Run a silent capture in the background
+///
+/// There is no window to report to, so the outcome is shown as a notification.
+pub fn silent_capture(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let notification = match run_silent_capture(&app).await {
+            Ok(uuid) => {
+                tracing::info!("Silently captured text into draft {}", uuid);
+                let body = "Saved the selection to a draft";
+                Notification::new(Category::Capture, "Captured", body)
+                    .action(Action::OpenDraft(uuid))
+            }
+            Err(e) => {
+                tracing::warn!("Silent capture failed: {}", e);
+                Notification::new(Category::Capture, "Capture failed", e)
+            }
+        };
+        notify::send(&app, notification);
+    });
+}
+
+async fn run_silent_capture(app: &AppHandle) -> Result<String, String> {
+    let text = tauri::async_runtime::spawn_blocking(read_selection)
+        .await
+        .map_err(|e| format!("Selection reader panicked: {}", e))??;
+
+    let pool = db::pool(app).await?;
+    let inbox = app.state::<SettingsState>().inbox_draft();
+    let (target, created) = resolve_target(&pool, inbox.as_deref()).await?;
+
+    let entry = format!("### {}\n{}", local_timestamp(&pool).await?, text);
+    let draft = drafts::append(&pool, &target, &entry).await?;
+
+    let kind = if created {
+        drafts::ChangeKind::Created
+    } else {
+        drafts::ChangeKind::Updated
+    };
+    drafts::notify(app, &draft.uuid, kind);
+    Ok(draft.uuid)
+}
+
+/// Pick the draft to append to: the configured inbox, then the pinned draft,
+/// then a brand new draft. Returns the UUID and whether it was just created.
+async fn resolve_target(pool: &SqlitePool, inbox: Option<&str>) -> Result<(String, bool), String> {
+    if let Some(uuid) = inbox {
+        match drafts::get(pool, uuid).await? {
+            Some(draft) if draft.deleted_at.is_none() => return Ok((draft.uuid, false)),
+            _ => tracing::warn!("Inbox draft {} not found, falling back to pinned draft", uuid),
+        }
+    }
+
+    if let Some(draft) = drafts::pinned(pool).await? {
+        return Ok((draft.uuid, false));
+    }
+
+    let draft = drafts::create(pool, "").await?;
+    Ok((draft.uuid, true))
+}
+
+/// Local wall-clock time for the entry heading
+async fn local_timestamp(pool: &SqlitePool) -> Result<String, String> {
+    sqlx::query_scalar("SELECT strftime('%Y-%m-%d %H:%M', 'now', 'localtime')")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to read local time: {}", e))
+}
+
+/// Read the primary selection, falling back to the clipboard
+fn read_selection() -> Result<String, String> {
+    for commands in [SELECTION_COMMANDS, CLIPBOARD_COMMANDS] {
+        for (program, args) in commands {
+            if let Some(text) = run_reader(program, args) {
+                return Ok(text);
+            }
+        }
+    }
+
+    Err("Nothing selected and clipboard is empty (is wl-paste, xclip or xsel installed?)".to_string())
+}
+
+/// Run a selection reader, returning its output if it produced any text
+fn run_reader(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
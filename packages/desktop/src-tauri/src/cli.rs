@@ -0,0 +1,421 @@
+//! Command-line interface.
+//!
+//! Headless subcommands such as `capture` and `list` work directly on the
+//! profile's database and exit without starting the webview. Writes are
+//! announced to the running app over the JSON-RPC socket, so its windows
+//! pick them up. Everything else launches the app, or forwards to the
+//! running instance.
+//!
+//! `--json` output serializes [`drafts::Draft`] as-is, so field names and
+//! order are stable for scripts.
+
+use crate::drafts::{self, ChangeKind, Draft, ListFilter};
+use crate::{db, profiles, rpc};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(name = "dashtext", about = "A text editor for quick capture")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// A dashtext:// link to open
+    #[arg(value_name = "URL")]
+    pub link: Option<String>,
+    /// Start with the main window hidden, leaving only hotkeys and the tray
+    #[arg(long)]
+    pub background: bool,
+    /// Write startup and hotkey timings as Chrome trace JSON, to FILE or the log directory
+    #[arg(long, value_name = "FILE", require_equals = true)]
+    pub trace: Option<Option<PathBuf>>,
+    /// Profile to use; each has its own drafts and settings
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+}
+
+impl Cli {
+    /// Parse launch arguments (without the program name)
+    pub fn try_parse_args(args: &[String]) -> Result<Self, clap::Error> {
+        Self::try_parse_from(std::iter::once("dashtext").chain(args.iter().map(String::as_str)))?
+            .checked()
+    }
+
+    /// Reject arguments that only apply to launching the app alongside a subcommand
+    ///
+    /// Checked after parsing because clap's `args_conflicts_with_subcommands`
+    /// would also reject `--profile` before the subcommand.
+    pub fn checked(self) -> Result<Self, clap::Error> {
+        let launch_only = self.link.is_some() || self.background || self.trace.is_some();
+        if self.command.is_some() && launch_only {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "a URL, --background and --trace can't be used with a subcommand",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create a draft from TEXT, or from stdin when no text is given
+    Capture {
+        /// Text to capture; multiple words are joined with spaces
+        text: Vec<String>,
+    },
+    /// List drafts, most recently modified first
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Print JSON instead of one line per draft
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a draft's content
+    Show {
+        /// UUID of the draft
+        uuid: String,
+        /// Print the whole draft as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List drafts containing QUERY
+    Search {
+        /// Text to look for (case-insensitive)
+        query: String,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Print JSON instead of one line per draft
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write each draft to its own file in a directory
+    Export {
+        /// File format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Md)]
+        format: ExportFormat,
+        /// Directory to write to; created if missing
+        #[arg(long)]
+        out: PathBuf,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Open a window in the app
+    Open {
+        #[command(subcommand)]
+        target: Option<OpenTarget>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OpenTarget {
+    /// The main editor window
+    Main,
+    /// The quick-capture window
+    Capture,
+    /// A draft in the main window
+    Draft {
+        /// UUID of the draft
+        uuid: String,
+    },
+}
+
+/// Which drafts to include besides active ones
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    /// Include archived drafts
+    #[arg(long)]
+    archived: bool,
+    /// Include deleted drafts
+    #[arg(long)]
+    deleted: bool,
+}
+
+impl From<&FilterArgs> for ListFilter {
+    fn from(args: &FilterArgs) -> Self {
+        Self {
+            archived: args.archived,
+            deleted: args.deleted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Markdown: the draft content as-is
+    Md,
+    /// JSON: the draft with its metadata
+    Json,
+}
+
+/// Run a headless subcommand
+///
+/// Returns the process exit code, or `None` when the app should start.
+pub fn run_headless(cli: &Cli) -> Option<i32> {
+    if let Err(e) = profiles::select(cli.profile.as_deref()) {
+        eprintln!("dashtext: {}", e);
+        return Some(1);
+    }
+
+    let result = match &cli.command {
+        Some(Command::Capture { text }) => capture(text),
+        Some(Command::List { filter, json }) => list(filter.into(), *json),
+        Some(Command::Show { uuid, json }) => show(uuid, *json),
+        Some(Command::Search {
+            query,
+            filter,
+            json,
+        }) => search(query, filter.into(), *json),
+        Some(Command::Export {
+            format,
+            out,
+            filter,
+        }) => export(*format, out, filter.into()),
+        Some(Command::Open { .. }) | None => return None,
+    };
+
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("dashtext: {}", e);
+            1
+        }
+    })
+}
+
+/// Create a draft and print its UUID
+fn capture(words: &[String]) -> Result<(), String> {
+    let text = if words.is_empty() {
+        read_stdin()?
+    } else {
+        words.join(" ")
+    };
+
+    if text.trim().is_empty() {
+        return Err("Nothing to capture".to_string());
+    }
+
+    let draft = tauri::async_runtime::block_on(async {
+        let pool = db::connect().await?;
+        let draft = drafts::create(&pool, text.trim_end()).await;
+        pool.close().await;
+        draft
+    })?;
+
+    println!("{}", draft.uuid);
+    // The draft is saved either way; a stale window is only worth a warning
+    if let Err(e) = rpc::announce(&draft.uuid, ChangeKind::Created) {
+        eprintln!("dashtext: {}", e);
+    }
+    Ok(())
+}
+
+fn list(filter: ListFilter, json: bool) -> Result<(), String> {
+    let drafts = read(|pool| async move { drafts::list(&pool, filter).await })?;
+    print_drafts(&drafts, json)
+}
+
+fn show(uuid: &str, json: bool) -> Result<(), String> {
+    let draft = read(|pool| async move { drafts::get(&pool, uuid).await })?
+        .ok_or_else(|| format!("Draft not found: {}", uuid))?;
+
+    if json {
+        print_json(&draft)
+    } else {
+        println!("{}", draft.content);
+        Ok(())
+    }
+}
+
+fn search(query: &str, filter: ListFilter, json: bool) -> Result<(), String> {
+    let drafts = read(|pool| async move { drafts::search(&pool, query, filter).await })?;
+    print_drafts(&drafts, json)
+}
+
+fn export(format: ExportFormat, out: &Path, filter: ListFilter) -> Result<(), String> {
+    let drafts = read(|pool| async move { drafts::list(&pool, filter).await })?;
+    write_exports(&drafts, format, out)?;
+    println!("Exported {} draft(s) to {}", drafts.len(), out.display());
+    Ok(())
+}
+
+/// Write each draft to `<uuid>.<extension>` in `out`
+fn write_exports(drafts: &[Draft], format: ExportFormat, out: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(out)
+        .map_err(|e| format!("Failed to create {}: {}", out.display(), e))?;
+
+    for draft in drafts {
+        let (extension, contents) = match format {
+            ExportFormat::Md => ("md", draft.content.clone()),
+            ExportFormat::Json => (
+                "json",
+                serde_json::to_string_pretty(draft)
+                    .map_err(|e| format!("Failed to serialize draft {}: {}", draft.uuid, e))?,
+            ),
+        };
+
+        let path = out.join(format!("{}.{}", draft.uuid, extension));
+        std::fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Run a query against a read-only connection to the app's database
+fn read<T, F, Fut>(query: F) -> Result<T, String>
+where
+    F: FnOnce(sqlx::SqlitePool) -> Fut,
+    Fut: std::future::Future<Output = Result<T, String>>,
+{
+    tauri::async_runtime::block_on(async {
+        let pool = db::connect_read_only().await?;
+        let result = query(pool.clone()).await;
+        pool.close().await;
+        result
+    })
+}
+
+/// Print drafts as JSON, or as `uuid  modified_at  title` lines
+fn print_drafts(drafts: &[Draft], json: bool) -> Result<(), String> {
+    if json {
+        return print_json(&drafts);
+    }
+
+    for draft in drafts {
+        println!("{}\t{}\t{}", draft.uuid, draft.modified_at, draft.title());
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err("No text given and nothing piped on stdin".to_string());
+    }
+
+    let mut text = String::new();
+    stdin
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn parse(args: &[&str]) -> Cli {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        Cli::try_parse_args(&args).unwrap()
+    }
+
+    #[test]
+    fn parses_capture() {
+        let Some(Command::Capture { text }) = parse(&["capture", "buy", "milk"]).command else {
+            panic!("expected capture");
+        };
+        assert_eq!(text, ["buy", "milk"]);
+
+        // No text means reading stdin
+        let Some(Command::Capture { text }) = parse(&["capture"]).command else {
+            panic!("expected capture");
+        };
+        assert!(text.is_empty());
+
+        let cli = parse(&["--profile", "work", "capture", "x"]);
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+        assert!(matches!(cli.command, Some(Command::Capture { .. })));
+    }
+
+    #[test]
+    fn parses_list_show_and_search() {
+        let Some(Command::List { filter, json }) = parse(&["list", "--archived", "--json"]).command
+        else {
+            panic!("expected list");
+        };
+        assert!(filter.archived && !filter.deleted && json);
+
+        let Some(Command::Show { uuid, json }) = parse(&["show", "abc"]).command else {
+            panic!("expected show");
+        };
+        assert_eq!(uuid, "abc");
+        assert!(!json);
+
+        let Some(Command::Search {
+            query,
+            filter,
+            json,
+        }) = parse(&["search", "two words", "--deleted"]).command
+        else {
+            panic!("expected search");
+        };
+        assert_eq!(query, "two words");
+        assert!(!filter.archived && filter.deleted && !json);
+
+        assert!(Cli::try_parse_args(&["show".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parses_export_and_open() {
+        let Some(Command::Export { format, out, .. }) = parse(&["export", "--out", "dir"]).command
+        else {
+            panic!("expected export");
+        };
+        assert!(matches!(format, ExportFormat::Md));
+        assert_eq!(out, Path::new("dir"));
+
+        let args = ["export", "--format", "json", "--out", "dir"];
+        let Some(Command::Export { format, .. }) = parse(&args).command else {
+            panic!("expected export");
+        };
+        assert!(matches!(format, ExportFormat::Json));
+
+        let args = ["export", "--format", "txt", "--out", "dir"].map(String::from);
+        assert!(Cli::try_parse_args(&args).is_err());
+        assert!(Cli::try_parse_args(&["export".to_string()]).is_err());
+
+        let Some(Command::Open { target }) = parse(&["open", "draft", "abc"]).command else {
+            panic!("expected open");
+        };
+        assert!(matches!(target, Some(OpenTarget::Draft { uuid }) if uuid == "abc"));
+        assert!(matches!(
+            parse(&["open"]).command,
+            Some(Command::Open { target: None })
+        ));
+    }
+
+    #[test]
+    fn exports_markdown_and_json() {
+        tauri::async_runtime::block_on(async {
+            let (dir, pool) = testing::pool().await;
+            let kept = drafts::create(&pool, "# Title\n\nbody").await.unwrap();
+            let archived = drafts::create(&pool, "old").await.unwrap();
+            drafts::archive(&pool, &archived.uuid).await.unwrap();
+            let drafts = drafts::list(&pool, ListFilter::default()).await.unwrap();
+
+            let out = dir.path().join("md");
+            write_exports(&drafts, ExportFormat::Md, &out).unwrap();
+            let md = std::fs::read_to_string(out.join(format!("{}.md", kept.uuid))).unwrap();
+            assert_eq!(md, "# Title\n\nbody");
+            assert_eq!(std::fs::read_dir(&out).unwrap().count(), 1);
+
+            let out = dir.path().join("json");
+            write_exports(&drafts, ExportFormat::Json, &out).unwrap();
+            let json = std::fs::read_to_string(out.join(format!("{}.json", kept.uuid))).unwrap();
+            let exported: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(exported, serde_json::to_value(&drafts[0]).unwrap());
+            assert_eq!(exported["content"], "# Title\n\nbody");
+        });
+    }
+}
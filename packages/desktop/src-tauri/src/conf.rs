@@ -1,4 +1,7 @@
-use crate::hotkey::{create_manager, HotkeyManager};
+use crate::hotkey::{create_manager, HotkeyAction, HotkeyManager};
+use crate::notify::{self, Category, Notification};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::AppHandle;
 use tokio::sync::Mutex;
@@ -6,7 +9,11 @@ use tokio::sync::Mutex;
 /// Application settings state
 /// Manages active hotkey registration (database persistence handled by frontend)
 pub struct SettingsState {
-    hotkey_manager: Mutex<Option<Arc<dyn HotkeyManager>>>,
+    hotkey_managers: Mutex<HashMap<HotkeyAction, Arc<dyn HotkeyManager>>>,
+    inbox_draft: std::sync::Mutex<Option<String>>,
+    hotkeys_paused: AtomicBool,
+    #[cfg(target_os = "linux")]
+    text_expander: Mutex<Option<Arc<crate::hotkey::TextExpander>>>,
     app: AppHandle,
 }
 
@@ -14,7 +21,11 @@ impl SettingsState {
     /// Create new settings state
     pub fn new(app: AppHandle) -> Self {
         Self {
-            hotkey_manager: Mutex::new(None),
+            hotkey_managers: Mutex::new(HashMap::new()),
+            inbox_draft: std::sync::Mutex::new(None),
+            hotkeys_paused: AtomicBool::new(false),
+            #[cfg(target_os = "linux")]
+            text_expander: Mutex::new(None),
             app,
         }
     }
@@ -22,32 +33,151 @@ impl SettingsState {
     /// Register capture shortcut hotkey
     /// This is called by the frontend after loading settings from the database
     pub async fn register_capture_shortcut(&self, shortcut: &str) -> Result<(), String> {
-        // Unregister existing hotkey if any
+        self.register_shortcut(HotkeyAction::Capture, shortcut).await
+    }
+
+    /// Unregister capture shortcut
+    pub async fn unregister_capture_shortcut(&self) -> Result<(), String> {
+        self.unregister_shortcut(HotkeyAction::Capture).await
+    }
+
+    /// Register silent capture shortcut hotkey
+    /// `inbox_uuid` is the draft to append to; when unset the pinned draft is used
+    pub async fn register_silent_capture_shortcut(
+        &self,
+        shortcut: &str,
+        inbox_uuid: Option<String>,
+    ) -> Result<(), String> {
+        *self.inbox_draft.lock().unwrap() = inbox_uuid;
+        self.register_shortcut(HotkeyAction::SilentCapture, shortcut).await
+    }
+
+    /// Unregister silent capture shortcut
+    pub async fn unregister_silent_capture_shortcut(&self) -> Result<(), String> {
+        self.unregister_shortcut(HotkeyAction::SilentCapture).await
+    }
+
+    /// Draft that silent captures are appended to, if one is configured
+    pub fn inbox_draft(&self) -> Option<String> {
+        self.inbox_draft.lock().unwrap().clone()
+    }
+
+    /// Pause or resume all hotkeys
+    /// Shortcuts stay registered while paused; they just don't trigger their action
+    pub fn set_hotkeys_paused(&self, paused: bool) {
+        self.hotkeys_paused.store(paused, Ordering::Relaxed);
+        tracing::info!("Hotkeys {}", if paused { "paused" } else { "resumed" });
+    }
+
+    /// Whether hotkeys are paused
+    pub fn hotkeys_paused(&self) -> bool {
+        self.hotkeys_paused.load(Ordering::Relaxed)
+    }
+
+    /// Enable text expansion, or update its exclusions and reload snippets if already enabled
+    /// `exclusions` are window classes in which triggers are ignored
+    pub async fn enable_text_expansion(&self, exclusions: Vec<String>) -> Result<(), String> {
+        #[cfg(target_os = "linux")]
         {
-            let mut mgr = self.hotkey_manager.lock().await;
-            if let Some(ref m) = *mgr {
-                m.unregister()?;
-            }
-            *mgr = None;
+            let mut expander = self.text_expander.lock().await;
+            let expander = match expander.as_ref() {
+                Some(e) => e.clone(),
+                None => expander
+                    .insert(Arc::new(crate::hotkey::TextExpander::new(self.app.clone())?))
+                    .clone(),
+            };
+            expander.start(exclusions).await
         }
 
-        // Register new hotkey
-        let mgr = create_manager(self.app.clone(), shortcut)?;
-        mgr.register()?;
-        *self.hotkey_manager.lock().await = Some(mgr);
-        
-        tracing::info!("Registered capture shortcut: {}", shortcut);
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = exclusions;
+            Err("Text expansion requires the evdev backend (Linux only)".to_string())
+        }
+    }
+
+    /// Disable text expansion
+    pub async fn disable_text_expansion(&self) -> Result<(), String> {
+        #[cfg(target_os = "linux")]
+        {
+            // Stopping joins the listener thread, so do it off the runtime and after releasing the lock
+            let expander = self.text_expander.lock().await.take();
+            if let Some(expander) = expander {
+                tauri::async_runtime::spawn_blocking(move || expander.stop())
+                    .await
+                    .map_err(|e| format!("Failed to stop text expansion: {}", e))?;
+            }
+        }
         Ok(())
     }
 
-    /// Unregister capture shortcut
-    pub async fn unregister_capture_shortcut(&self) -> Result<(), String> {
-        let mut mgr = self.hotkey_manager.lock().await;
-        if let Some(ref m) = *mgr {
-            m.unregister()?;
-            tracing::info!("Unregistered capture shortcut");
+    /// Pause or resume text expansion without disabling it
+    pub async fn set_text_expansion_paused(&self, paused: bool) -> Result<(), String> {
+        #[cfg(target_os = "linux")]
+        if let Some(ref expander) = *self.text_expander.lock().await {
+            expander.set_paused(paused);
+            return Ok(());
+        }
+
+        let _ = paused;
+        Err("Text expansion is not enabled".to_string())
+    }
+
+    /// Unregister every hotkey and stop text expansion, waiting for their threads to finish
+    ///
+    /// Used at shutdown and when switching profiles, so the inbox draft is forgotten too.
+    pub async fn unregister_all(&self) {
+        *self.inbox_draft.lock().unwrap() = None;
+        let managers: Vec<_> = self.hotkey_managers.lock().await.drain().collect();
+        for (action, manager) in managers {
+            match manager.unregister() {
+                Ok(()) => tracing::info!("Unregistered {:?} shortcut", action),
+                Err(e) => tracing::warn!("Failed to unregister {:?} shortcut: {}", action, e),
+            }
         }
-        *mgr = None;
+
+        let _ = self.disable_text_expansion().await;
+    }
+
+    /// Register the hotkey for an action, replacing any existing registration
+    ///
+    /// The lock is held throughout, so concurrent calls can't both register
+    /// and leave one manager running unreachable.
+    async fn register_shortcut(&self, action: HotkeyAction, shortcut: &str) -> Result<(), String> {
+        let mut managers = self.hotkey_managers.lock().await;
+        unregister_locked(&mut managers, action)?;
+
+        // Register new hotkey, telling the user if it fails since no window may be open
+        let span = tracing::debug_span!("register_hotkey", ?action, shortcut);
+        let mgr = span
+            .in_scope(|| {
+                create_manager(self.app.clone(), shortcut, action)
+                    .and_then(|mgr| mgr.register().map(|_| mgr))
+            })
+            .inspect_err(|e| {
+                let summary = format!("Couldn't register shortcut {}", shortcut);
+                notify::send(&self.app, Notification::new(Category::Hotkey, summary, e));
+            })?;
+        managers.insert(action, mgr);
+
+        tracing::info!("Registered {:?} shortcut: {}", action, shortcut);
         Ok(())
     }
+
+    /// Unregister the hotkey for an action
+    async fn unregister_shortcut(&self, action: HotkeyAction) -> Result<(), String> {
+        unregister_locked(&mut *self.hotkey_managers.lock().await, action)
+    }
+}
+
+/// Unregister the hotkey for an action from the locked manager map
+fn unregister_locked(
+    managers: &mut HashMap<HotkeyAction, Arc<dyn HotkeyManager>>,
+    action: HotkeyAction,
+) -> Result<(), String> {
+    if let Some(m) = managers.remove(&action) {
+        m.unregister()?;
+        tracing::info!("Unregistered {:?} shortcut", action);
+    }
+    Ok(())
 }
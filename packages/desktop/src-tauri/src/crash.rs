@@ -0,0 +1,210 @@
+//! Local crash reports.
+//!
+//! A panic hook writes a report to `crashes/` in the app data dir with the
+//! backtrace, version, platform, hotkey backend and recent log lines. On the
+//! next start the frontend asks for the newest report it hasn't shown yet and
+//! offers to view, copy or delete it. Reports never leave the machine.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri_plugin_log::fern;
+use tauri_plugin_log::{Target, TargetKind};
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// Number of recent log lines kept for crash reports
+const RECENT_LINES: usize = 200;
+
+/// Extension of crash report files
+const REPORT_EXTENSION: &str = "txt";
+
+/// Extension of the marker written once a report has been shown
+const SEEN_EXTENSION: &str = "seen";
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// A crash report on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    /// File name, used to refer to the report in commands
+    pub name: String,
+    pub path: String,
+    pub contents: String,
+}
+
+/// Log target that keeps the last lines in memory for crash reports
+pub fn log_target() -> Target {
+    let dispatch = fern::Dispatch::new().chain(fern::Output::call(|record| {
+        let line = format!("{} {} {}", record.level(), record.target(), record.args());
+        let mut recent = RECENT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }));
+    Target::new(TargetKind::Dispatch(dispatch))
+}
+
+/// Write a crash report for every panic, then run the default hook
+pub fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Ok(path) => eprintln!("dashtext: crash report written to {}", path.display()),
+            Err(e) => eprintln!("dashtext: failed to write crash report: {}", e),
+        }
+        previous(info);
+    }));
+}
+
+/// Directory crash reports are written to
+pub fn crash_dir() -> Result<PathBuf, String> {
+    Ok(crate::paths::app_data_dir()?.join("crashes"))
+}
+
+fn write_report(info: &PanicHookInfo<'_>) -> Result<PathBuf, String> {
+    let now = OffsetDateTime::now_utc();
+    let stamp = now
+        .format(format_description!(
+            "[year][month][day]-[hour][minute][second]-[subsecond digits:3]"
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<non-string panic payload>".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let thread = std::thread::current()
+        .name()
+        .unwrap_or("<unnamed>")
+        .to_string();
+
+    // The panic may have happened while logging, so don't wait for the lock
+    let recent = match RECENT_LOG.try_lock() {
+        Ok(lines) => lines.iter().cloned().collect::<Vec<_>>().join("\n"),
+        Err(_) => "<log unavailable>".to_string(),
+    };
+
+    let report = format!(
+        "DashText crash report\n\n\
+         Time: {}\n\
+         Version: {}\n\
+         Platform: {}\n\
+         Hotkey backend: {}\n\
+         Thread: {}\n\
+         Location: {}\n\
+         Message: {}\n\n\
+         Backtrace:\n{}\n\n\
+         Recent log:\n{}\n",
+        now,
+        env!("CARGO_PKG_VERSION"),
+        crate::updater::get_platform_key(),
+        crate::hotkey::active_backend(),
+        thread,
+        location,
+        message,
+        std::backtrace::Backtrace::force_capture(),
+        recent,
+    );
+
+    let dir = crash_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let path = dir.join(format!("crash-{}.{}", stamp, REPORT_EXTENSION));
+    fs::write(&path, report).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(path)
+}
+
+/// Path of a report by name, rejecting anything outside the crash dir
+fn report_path(name: &str) -> Result<PathBuf, String> {
+    let valid = name.starts_with("crash-")
+        && name.ends_with(&format!(".{}", REPORT_EXTENSION))
+        && !name.contains(['/', '\\'])
+        && !name.contains("..");
+    if !valid {
+        return Err(format!("Invalid crash report name: {}", name));
+    }
+    Ok(crash_dir()?.join(name))
+}
+
+/// The newest report that hasn't been shown yet
+pub fn unseen_report() -> Result<Option<CrashReport>, String> {
+    let dir = crash_dir()?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(None);
+    };
+
+    // Names embed the time, so the greatest is the newest
+    let newest = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == REPORT_EXTENSION)
+                && !path.with_extension(SEEN_EXTENSION).exists()
+        })
+        .max();
+
+    let Some(path) = newest else {
+        return Ok(None);
+    };
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    Ok(Some(CrashReport {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.display().to_string(),
+        contents,
+    }))
+}
+
+/// Don't offer a report again on later starts
+pub fn mark_seen(name: &str) -> Result<(), String> {
+    let marker = report_path(name)?.with_extension(SEEN_EXTENSION);
+    fs::write(&marker, "").map_err(|e| format!("Failed to write {:?}: {}", marker, e))
+}
+
+/// Delete a report
+pub fn delete(name: &str) -> Result<(), String> {
+    let path = report_path(name)?;
+    let _ = fs::remove_file(path.with_extension(SEEN_EXTENSION));
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_report_names_inside_the_crash_dir() {
+        let name = "crash-20261018-174500-123.txt";
+        assert_eq!(report_path(name), Ok(crash_dir().unwrap().join(name)));
+    }
+
+    #[test]
+    fn rejects_report_names_that_leave_the_crash_dir() {
+        for name in [
+            "..",
+            "../crash-1.txt",
+            "crash-/../../secrets.txt",
+            "crash-..\\..\\x.txt",
+            "crash-...txt",
+            "/etc/crash-1.txt",
+            "notes.txt",
+            "crash-1.json",
+            "",
+        ] {
+            assert!(report_path(name).is_err(), "{} was accepted", name);
+        }
+    }
+}
@@ -6,21 +6,208 @@
 //! 3. Add the generated SQL file to `get_migrations()` below
 //! 4. Increment the version number
 //!
-//! Migrations are stored in @dashtext/lib and shared between desktop and web.
+//! Migrations are stored in @dashtext/lib and shared between desktop and web,
+//! except for desktop-only ones in `src-tauri/migrations`, which use SQLite
+//! features the web app's sql.js build lacks (such as FTS5). Those are only
+//! listed here, not in the shared Drizzle journal.
 
-use tauri::{plugin::TauriPlugin, Runtime};
-use tauri_plugin_sql::{Migration, MigrationKind, PluginConfig};
+use crate::{paths, profiles};
+use serde::Serialize;
+use sqlx::migrate::{Migration as SqlxMigration, MigrationSource, MigrationType, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::path::PathBuf;
+use tauri::{plugin::TauriPlugin, AppHandle, Manager, Runtime};
+use tauri_plugin_sql::{DbInstances, DbPool, Migration, MigrationKind, PluginConfig};
+use tracing::Instrument;
 
-/// Database connection URL
-pub const DB_URL: &str = "sqlite:dashtext.db";
+/// Database connection URL of the current profile
+pub fn url() -> String {
+    profiles::db_url(&profiles::current())
+}
 
-/// Returns the configured SQL plugin with migrations.
+/// Returns the configured SQL plugin with migrations for the current profile.
+///
+/// Profiles switched to later are migrated by [`open`] instead.
 pub fn plugin<R: Runtime>() -> TauriPlugin<R, Option<PluginConfig>> {
     tauri_plugin_sql::Builder::default()
-        .add_migrations(DB_URL, get_migrations())
+        .add_migrations(&url(), get_migrations())
         .build()
 }
 
+/// Returns the SQLite pool the SQL plugin opened for the current profile.
+///
+/// The pool is shared with the frontend's drizzle proxy, so Rust writes go
+/// through the same connection pool and migrations as everything else.
+pub async fn pool<R: Runtime>(app: &AppHandle<R>) -> Result<SqlitePool, String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "SQL plugin is not initialized".to_string())?;
+    let instances = instances.0.read().await;
+
+    let url = url();
+    match instances.get(&url) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        _ => Err(format!("Database {} is not loaded", url)),
+    }
+}
+
+/// Hand a pool to the SQL plugin, so Rust code can use it before the frontend loads it
+pub async fn install<R: Runtime>(
+    app: &AppHandle<R>,
+    url: &str,
+    pool: SqlitePool,
+) -> Result<(), String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "SQL plugin is not initialized".to_string())?;
+    instances
+        .0
+        .write()
+        .await
+        .insert(url.to_string(), DbPool::Sqlite(pool));
+    Ok(())
+}
+
+/// Close a database's pool, waiting for its connections to finish
+pub async fn close<R: Runtime>(app: &AppHandle<R>, url: &str) -> Result<(), String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "SQL plugin is not initialized".to_string())?;
+    let Some(DbPool::Sqlite(pool)) = instances.0.write().await.remove(url) else {
+        return Ok(());
+    };
+
+    pool.close().await;
+    tracing::info!("Database {} closed", url);
+    Ok(())
+}
+
+/// Path of the database file the SQL plugin opens for `url`.
+///
+/// The plugin resolves relative SQLite URLs against Tauri's app config dir;
+/// absolute ones, used in portable mode, are taken as-is.
+fn path_for(url: &str) -> Result<PathBuf, String> {
+    Ok(paths::app_config_dir()?.join(url.trim_start_matches("sqlite:")))
+}
+
+/// Path of the current profile's database file.
+pub fn database_path() -> Result<PathBuf, String> {
+    path_for(&url())
+}
+
+/// Open the current profile's database without a running app, applying the same migrations the SQL plugin does.
+///
+/// Used by headless CLI commands. Migration bookkeeping lives in the same
+/// `_sqlx_migrations` table, so the app and CLI can open one file in any order.
+pub async fn connect() -> Result<SqlitePool, String> {
+    open(&url()).await
+}
+
+/// Open the database for a SQL plugin URL, creating and migrating it as needed
+pub async fn open(url: &str) -> Result<SqlitePool, String> {
+    let path = path_for(url)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create database directory: {}", e))?;
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    migrate(&pool)
+        .instrument(tracing::debug_span!("migrate", url))
+        .await?;
+
+    Ok(pool)
+}
+
+/// Apply any of [`get_migrations`] the database doesn't have yet
+pub async fn migrate(pool: &SqlitePool) -> Result<(), String> {
+    let migrator = Migrator::new(Migrations(get_migrations()))
+        .await
+        .map_err(|e| format!("Failed to load migrations: {}", e))?;
+    migrator
+        .run(pool)
+        .await
+        .map_err(|e| format!("Failed to apply migrations: {}", e))
+}
+
+/// Open the database read-only without a running app.
+///
+/// Safe to use while the app has the file open: readers wait out the app's
+/// writes via SQLite's busy timeout rather than failing.
+pub async fn connect_read_only() -> Result<SqlitePool, String> {
+    let path = database_path()?;
+    if !path.exists() {
+        return Err(format!("No database found at {}", path.display()));
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(&path)
+        .read_only(true)
+        .busy_timeout(std::time::Duration::from_secs(10));
+    SqlitePool::connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// A migration recorded as applied in `_sqlx_migrations`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: String,
+    pub success: bool,
+}
+
+/// Versions of all migrations this build knows about
+pub fn known_migration_versions() -> Vec<i64> {
+    get_migrations().into_iter().map(|m| m.version).collect()
+}
+
+/// Migrations applied to the database, oldest first
+pub async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<AppliedMigration>, String> {
+    sqlx::query_as(
+        "SELECT version, description, CAST(installed_on AS TEXT) AS installed_on, success
+         FROM _sqlx_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read applied migrations: {}", e))
+}
+
+/// [`get_migrations`] as an sqlx migration source, converted the same way the SQL plugin does
+#[derive(Debug)]
+struct Migrations(Vec<Migration>);
+
+impl MigrationSource<'static> for Migrations {
+    fn resolve(
+        self,
+    ) -> futures_util::future::BoxFuture<'static, Result<Vec<SqlxMigration>, sqlx::error::BoxDynError>>
+    {
+        Box::pin(async move {
+            Ok(self
+                .0
+                .into_iter()
+                .filter(|m| matches!(m.kind, MigrationKind::Up))
+                .map(|m| {
+                    SqlxMigration::new(
+                        m.version,
+                        m.description.into(),
+                        MigrationType::ReversibleUp,
+                        m.sql.into(),
+                        false,
+                    )
+                })
+                .collect())
+        })
+    }
+}
+
 /// Returns all database migrations in order.
 fn get_migrations() -> Vec<Migration> {
     vec![
@@ -48,5 +235,35 @@ fn get_migrations() -> Vec<Migration> {
             sql: include_str!("../../../lib/src/db/migrations/0003_add_automerge_tables.sql"),
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 5,
+            description: "add_single_pin_index",
+            sql: include_str!("../../../lib/src/db/migrations/0004_single_pinned_draft.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "add_draft_fts",
+            sql: include_str!("../migrations/draft_fts.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "add_draft_revisions",
+            sql: include_str!("../../../lib/src/db/migrations/0005_add_draft_revisions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add_silent_capture_settings",
+            sql: include_str!("../../../lib/src/db/migrations/0006_add_silent_capture_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add_text_expansion_settings",
+            sql: include_str!("../../../lib/src/db/migrations/0007_add_text_expansion_settings.sql"),
+            kind: MigrationKind::Up,
+        },
     ]
 }
@@ -0,0 +1,198 @@
+//! D-Bus session service for desktop integration.
+//!
+//! Owns `app.dashtext.DashText` on the session bus, so desktop shortcut
+//! settings can bind keys to commands like:
+//!
+//! ```text
+//! gdbus call --session --dest app.dashtext.DashText \
+//!     --object-path /app/dashtext/DashText \
+//!     --method app.dashtext.DashText.ShowCapture
+//! ```
+//!
+//! The bus comes from `DBUS_SESSION_BUS_ADDRESS`, so pointing that at a
+//! private `dbus-daemon` is enough to exercise the service in isolation.
+
+use crate::drafts::{self, ChangeKind, DraftChange};
+use crate::{db, windows};
+use tauri::{AppHandle, Listener, Runtime};
+use zbus::connection::Builder;
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, Connection};
+
+/// Well-known name owned on the session bus
+pub const BUS_NAME: &str = "app.dashtext.DashText";
+
+/// Path the service object is exported at
+pub const OBJECT_PATH: &str = "/app/dashtext/DashText";
+
+struct Service<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+#[zbus::interface(name = "app.dashtext.DashText")]
+impl<R: Runtime> Service<R> {
+    /// Open the quick-capture window
+    async fn show_capture(&self) {
+        windows::show_capture(&self.app);
+    }
+
+    /// Show and focus the main window
+    async fn show_main(&self) -> fdo::Result<()> {
+        windows::show_main(&self.app).map_err(fdo::Error::Failed)
+    }
+
+    /// Create a draft and return its UUID
+    async fn create_draft(&self, text: String) -> fdo::Result<String> {
+        let pool = db::pool(&self.app).await.map_err(fdo::Error::Failed)?;
+        let draft = drafts::create(&pool, &text)
+            .await
+            .map_err(fdo::Error::Failed)?;
+        drafts::notify(&self.app, &draft.uuid, ChangeKind::Created);
+        Ok(draft.uuid)
+    }
+
+    /// Append text to the pinned draft and return its UUID
+    async fn append_to_pinned(&self, text: String) -> fdo::Result<String> {
+        let pool = db::pool(&self.app).await.map_err(fdo::Error::Failed)?;
+        let pinned = drafts::pinned(&pool)
+            .await
+            .map_err(fdo::Error::Failed)?
+            .ok_or_else(|| fdo::Error::Failed("No draft is pinned".to_string()))?;
+        let draft = drafts::append(&pool, &pinned.uuid, &text)
+            .await
+            .map_err(fdo::Error::Failed)?;
+        drafts::notify(&self.app, &draft.uuid, ChangeKind::Updated);
+        Ok(draft.uuid)
+    }
+
+    /// A draft was created
+    #[zbus(signal)]
+    async fn draft_created(emitter: &SignalEmitter<'_>, uuid: &str) -> zbus::Result<()>;
+
+    /// A draft's content or state changed
+    #[zbus(signal)]
+    async fn draft_changed(emitter: &SignalEmitter<'_>, uuid: &str) -> zbus::Result<()>;
+
+    /// A draft was deleted for good
+    #[zbus(signal)]
+    async fn draft_deleted(emitter: &SignalEmitter<'_>, uuid: &str) -> zbus::Result<()>;
+}
+
+/// Start the service on the session bus in the background
+pub fn serve(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let result = match Builder::session() {
+            Ok(builder) => start(app, builder).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("D-Bus service unavailable: {}", e);
+        }
+    });
+}
+
+/// Export the service on the bus `builder` connects to and forward draft changes as signals
+pub async fn start<R: Runtime>(app: AppHandle<R>, builder: Builder<'_>) -> zbus::Result<Connection> {
+    let connection = builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Service { app: app.clone() })?
+        .build()
+        .await?;
+    tracing::info!("Owning {} on D-Bus", BUS_NAME);
+
+    // The listener keeps a handle to the connection, so it stays up for the life of the app
+    let conn = connection.clone();
+    app.listen(drafts::CHANGED_EVENT, move |event| {
+        let Ok(change) = serde_json::from_str::<DraftChange>(event.payload()) else {
+            return;
+        };
+        let conn = conn.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = emit_change::<R>(&conn, &change).await {
+                tracing::warn!("Failed to emit D-Bus signal: {}", e);
+            }
+        });
+    });
+
+    Ok(connection)
+}
+
+async fn emit_change<R: Runtime>(conn: &Connection, change: &DraftChange) -> zbus::Result<()> {
+    let emitter = SignalEmitter::new(conn, OBJECT_PATH)?;
+    match change.kind {
+        ChangeKind::Created => Service::<R>::draft_created(&emitter, &change.uuid).await,
+        ChangeKind::Updated => Service::<R>::draft_changed(&emitter, &change.uuid).await,
+        ChangeKind::Deleted => Service::<R>::draft_deleted(&emitter, &change.uuid).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestBus};
+    use futures_util::StreamExt;
+    use std::time::Duration;
+    use tauri::Manager;
+    use tauri_plugin_sql::DbInstances;
+    use zbus::Proxy;
+    use zbus::proxy::SignalStream;
+
+    /// UUID carried by the next signal on `stream`
+    async fn next_uuid(stream: &mut SignalStream<'_>) -> String {
+        let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("signal within 5s")
+            .expect("signal stream open");
+        message.body().deserialize().expect("uuid argument")
+    }
+
+    #[test]
+    fn serves_methods_and_signals() {
+        let bus = TestBus::start();
+
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            let (_dir, pool) = testing::pool().await;
+            app.manage(DbInstances::default());
+            db::install(app.handle(), &db::url(), pool.clone())
+                .await
+                .unwrap();
+
+            let builder = Builder::address(bus.address.as_str()).unwrap();
+            let _service = start(app.handle().clone(), builder).await.unwrap();
+
+            let client = Builder::address(bus.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let proxy = Proxy::new(&client, BUS_NAME, OBJECT_PATH, BUS_NAME)
+                .await
+                .unwrap();
+            let mut created = proxy.receive_signal("DraftCreated").await.unwrap();
+            let mut changed = proxy.receive_signal("DraftChanged").await.unwrap();
+
+            proxy.call::<_, _, ()>("ShowCapture", &()).await.unwrap();
+            assert!(app.get_webview_window(windows::CAPTURE).is_some());
+
+            let uuid: String = proxy.call("CreateDraft", &("first",)).await.unwrap();
+            assert_eq!(next_uuid(&mut created).await, uuid);
+
+            let unpinned = proxy.call::<_, _, String>("AppendToPinned", &("lost",));
+            assert!(unpinned.await.is_err());
+
+            drafts::pin(&pool, &uuid).await.unwrap();
+            let appended: String = proxy.call("AppendToPinned", &("second",)).await.unwrap();
+            assert_eq!(appended, uuid);
+            assert_eq!(next_uuid(&mut changed).await, uuid);
+            let draft = drafts::get(&pool, &uuid).await.unwrap().unwrap();
+            assert_eq!(draft.content, "first\n\nsecond");
+
+            drafts::delete(&pool, &uuid).await.unwrap();
+            let trashed = proxy.call::<_, _, String>("AppendToPinned", &("lost",));
+            assert!(trashed.await.is_err());
+            let draft = drafts::get(&pool, &uuid).await.unwrap().unwrap();
+            assert_eq!(draft.content, "first\n\nsecond");
+        });
+    }
+}
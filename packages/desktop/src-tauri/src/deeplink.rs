@@ -0,0 +1,288 @@
+//! `dashtext://` deep links.
+//!
+//! Supported forms:
+//! - `dashtext://capture` opens the quick-capture window
+//! - `dashtext://capture?text=...&tags=a,b` creates a draft and opens it
+//! - `dashtext://draft/<uuid>` opens a draft in the main window
+//! - `dashtext://action/<name>?uuid=<uuid>` pins, unpins, archives,
+//!   unarchives or restores a draft
+//!
+//! Any web page can open a link, so actions are limited to [`Action`]'s
+//! allow-list of changes that are easy to undo and lose nothing; deleting
+//! through a link is refused.
+//!
+//! Links reach the app as a launch argument, so they go through the same
+//! single-instance forwarding as any other command line.
+
+use crate::drafts::{self, ChangeKind};
+use crate::{db, windows};
+use tauri::AppHandle;
+use url::Url;
+
+/// URL scheme handled by the app
+pub const SCHEME: &str = "dashtext";
+
+/// Largest `text` accepted from a capture link, in bytes
+const MAX_TEXT_LEN: usize = 1024 * 1024;
+
+/// A parsed and validated deep link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// Open the capture window, or create a draft when text is given
+    Capture { text: Option<String>, tags: Vec<String> },
+    /// Open a draft in the main window
+    Draft(String),
+    /// Change a draft's state
+    Action { action: Action, uuid: String },
+}
+
+/// State changes allowed through `dashtext://action/<name>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Pin,
+    Unpin,
+    Archive,
+    Unarchive,
+    /// Bring a draft back from the trash
+    Restore,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "pin" => Ok(Action::Pin),
+            "unpin" => Ok(Action::Unpin),
+            "archive" => Ok(Action::Archive),
+            "unarchive" => Ok(Action::Unarchive),
+            "restore" => Ok(Action::Restore),
+            "delete" | "hard-delete" | "empty-trash" => {
+                Err(format!("Action {} isn't allowed from links", name))
+            }
+            _ => Err(format!("Unknown action: {}", name)),
+        }
+    }
+}
+
+impl DeepLink {
+    /// Parse and validate a `dashtext://` URL
+    pub fn parse(link: &str) -> Result<Self, String> {
+        let url = Url::parse(link).map_err(|e| format!("Invalid link {:?}: {}", link, e))?;
+        if url.scheme() != SCHEME {
+            return Err(format!("Not a {}:// link: {}", SCHEME, link));
+        }
+
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        match (host.as_str(), segments.as_slice()) {
+            ("capture", []) => {
+                let text = query(&url, "text").filter(|t| !t.trim().is_empty());
+                if text.as_ref().is_some_and(|t| t.len() > MAX_TEXT_LEN) {
+                    return Err(format!("Capture text exceeds {} bytes", MAX_TEXT_LEN));
+                }
+                let tags = match query(&url, "tags") {
+                    Some(tags) => parse_tags(&tags)?,
+                    None => Vec::new(),
+                };
+                Ok(DeepLink::Capture { text, tags })
+            }
+            ("draft", [uuid]) => Ok(DeepLink::Draft(parse_uuid(uuid)?)),
+            ("action", [name]) => {
+                let action = Action::from_name(name)?;
+                let uuid = query(&url, "uuid").ok_or_else(|| "Missing uuid".to_string())?;
+                Ok(DeepLink::Action {
+                    action,
+                    uuid: parse_uuid(&uuid)?,
+                })
+            }
+            _ => Err(format!("Unsupported link: {}", link)),
+        }
+    }
+
+    /// Carry out the link in the running app
+    pub async fn dispatch(&self, app: &AppHandle) -> Result<(), String> {
+        match self {
+            DeepLink::Capture { text: None, .. } => {
+                windows::show_capture(app);
+                Ok(())
+            }
+            DeepLink::Capture {
+                text: Some(text),
+                tags,
+            } => {
+                let pool = db::pool(app).await?;
+                let draft = drafts::create(&pool, &with_tags(text, tags)).await?;
+                drafts::notify(app, &draft.uuid, ChangeKind::Created);
+                windows::open_draft(app, &draft.uuid)
+            }
+            DeepLink::Draft(uuid) => windows::open_draft(app, uuid),
+            DeepLink::Action { action, uuid } => {
+                let pool = db::pool(app).await?;
+                let draft = match action {
+                    Action::Pin => {
+                        let change = drafts::pin(&pool, uuid).await?;
+                        if let Some(previous) = change.unpinned() {
+                            drafts::notify(app, previous, ChangeKind::Updated);
+                        }
+                        change.draft
+                    }
+                    Action::Unpin => drafts::unpin(&pool, uuid).await?,
+                    Action::Archive => drafts::archive(&pool, uuid).await?,
+                    Action::Unarchive => drafts::unarchive(&pool, uuid).await?,
+                    Action::Restore => drafts::restore(&pool, uuid).await?,
+                };
+                drafts::notify(app, &draft.uuid, ChangeKind::Updated);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// First value of a query parameter, percent-decoded
+fn query(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn parse_uuid(value: &str) -> Result<String, String> {
+    uuid::Uuid::parse_str(value)
+        .map(|uuid| uuid.hyphenated().to_string())
+        .map_err(|_| format!("Invalid draft UUID: {}", value))
+}
+
+/// Split comma-separated tags, allowing letters, digits, `-`, `_` and `/`
+fn parse_tags(tags: &str) -> Result<Vec<String>, String> {
+    tags.split(',')
+        .map(|tag| tag.trim().trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| {
+            if tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+            {
+                Ok(tag.to_string())
+            } else {
+                Err(format!("Invalid tag: {}", tag))
+            }
+        })
+        .collect()
+}
+
+/// Append tags to captured text as a line of hashtags
+fn with_tags(text: &str, tags: &[String]) -> String {
+    let text = text.trim_end();
+    if tags.is_empty() {
+        return text.to_string();
+    }
+
+    let tags: Vec<String> = tags.iter().map(|tag| format!("#{}", tag)).collect();
+    format!("{}\n\n{}", text, tags.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "0b7e7d5c-3c39-4c8e-9a4e-3f0f6a8e2d11";
+
+    #[test]
+    fn parses_capture() {
+        assert_eq!(
+            DeepLink::parse("dashtext://capture"),
+            Ok(DeepLink::Capture {
+                text: None,
+                tags: Vec::new()
+            })
+        );
+        assert_eq!(
+            DeepLink::parse("dashtext://capture?text=hello%20world&tags=a,%23b,,c/d"),
+            Ok(DeepLink::Capture {
+                text: Some("hello world".to_string()),
+                tags: vec!["a".to_string(), "b".to_string(), "c/d".to_string()]
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_blank_capture_text() {
+        assert_eq!(
+            DeepLink::parse("dashtext://capture?text=%20%0A"),
+            Ok(DeepLink::Capture {
+                text: None,
+                tags: Vec::new()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_tags() {
+        assert!(DeepLink::parse("dashtext://capture?text=x&tags=a%20b").is_err());
+        assert!(DeepLink::parse("dashtext://capture?text=x&tags=a;b").is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_text() {
+        let link = format!("dashtext://capture?text={}", "a".repeat(MAX_TEXT_LEN + 1));
+        assert!(DeepLink::parse(&link).is_err());
+    }
+
+    #[test]
+    fn parses_draft() {
+        let link = format!("dashtext://draft/{}", UUID.to_uppercase());
+        assert_eq!(DeepLink::parse(&link), Ok(DeepLink::Draft(UUID.to_string())));
+        assert!(DeepLink::parse("dashtext://draft/not-a-uuid").is_err());
+        assert!(DeepLink::parse("dashtext://draft").is_err());
+    }
+
+    #[test]
+    fn parses_allowed_actions() {
+        for (name, action) in [
+            ("pin", Action::Pin),
+            ("unpin", Action::Unpin),
+            ("archive", Action::Archive),
+            ("unarchive", Action::Unarchive),
+            ("restore", Action::Restore),
+        ] {
+            let link = format!("dashtext://action/{}?uuid={}", name, UUID);
+            assert_eq!(
+                DeepLink::parse(&link),
+                Ok(DeepLink::Action {
+                    action,
+                    uuid: UUID.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_destructive_and_unknown_actions() {
+        for name in ["delete", "hard-delete", "empty-trash"] {
+            let link = format!("dashtext://action/{}?uuid={}", name, UUID);
+            let error = DeepLink::parse(&link).unwrap_err();
+            assert!(error.contains("isn't allowed"), "{}", error);
+        }
+        let link = format!("dashtext://action/rename?uuid={}", UUID);
+        assert_eq!(DeepLink::parse(&link).unwrap_err(), "Unknown action: rename");
+        assert!(DeepLink::parse("dashtext://action/pin").is_err());
+        assert!(DeepLink::parse("dashtext://action/pin?uuid=nope").is_err());
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        assert!(DeepLink::parse("https://capture").is_err());
+        assert!(DeepLink::parse("dashtext://settings").is_err());
+        assert!(DeepLink::parse("dashtext://capture/extra").is_err());
+        assert!(DeepLink::parse("not a link").is_err());
+    }
+
+    #[test]
+    fn appends_tags_to_text() {
+        let tags = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(with_tags("note\n", &tags), "note\n\n#a #b");
+        assert_eq!(with_tags("note", &[]), "note");
+    }
+}
@@ -0,0 +1,140 @@
+//! Freedesktop `.desktop` entry for the installed binary.
+//!
+//! The entry advertises `x-scheme-handler/dashtext`, so writing it and
+//! making it the default handler is what lets browsers open
+//! `dashtext://` links in the app.
+
+use crate::deeplink::SCHEME;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// File name of the entry in the applications directory
+pub const FILE_NAME: &str = "dashtext.desktop";
+
+/// Path of the per-user entry, `~/.local/share/applications/dashtext.desktop`
+pub fn path() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("applications").join(FILE_NAME))
+        .ok_or_else(|| "Could not determine data directory".to_string())
+}
+
+/// Render the application entry for the running executable
+pub fn render() -> Result<String, String> {
+    Ok(format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=DashText\n\
+         Comment=A text editor for quick capture\n\
+         Exec={} %u\n\
+         Terminal=false\n\
+         Categories=Utility;TextEditor;\n\
+         MimeType=x-scheme-handler/{};\n\
+         StartupWMClass=dashtext\n",
+        exec()?,
+        SCHEME
+    ))
+}
+
+/// Render an autostart entry that starts the running executable in the background
+///
+/// Only for `~/.config/autostart`: it takes no URL and handles no links, so
+/// it can't compete with [`render`]'s entry as the `dashtext://` handler.
+pub fn render_autostart() -> Result<String, String> {
+    Ok(format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=DashText\n\
+         Exec={} --background\n\
+         Terminal=false\n",
+        exec()?
+    ))
+}
+
+/// Quoted path of the running executable, for `Exec`
+fn exec() -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to get current exe path: {}", e))?;
+    Ok(quote(&exe.to_string_lossy()))
+}
+
+/// Write the entry and make it the default `dashtext://` handler
+pub fn register_url_handler() -> Result<PathBuf, String> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    fs::write(&path, render()?).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    let mime = format!("x-scheme-handler/{}", SCHEME);
+    let status = Command::new("xdg-mime")
+        .args(["default", FILE_NAME, &mime])
+        .status()
+        .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+    if !status.success() {
+        return Err(format!("xdg-mime exited with {}", status));
+    }
+
+    // Optional: refreshes the MIME cache on desktops that read it
+    if let Some(dir) = path.parent() {
+        let _ = Command::new("update-desktop-database").arg(dir).status();
+    }
+
+    tracing::info!("Registered {} as the {} handler", path.display(), mime);
+    Ok(path)
+}
+
+/// Quote an `Exec` argument per the Desktop Entry spec
+fn quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if !arg.contains(|c: char| c.is_whitespace() || "\"'\\`$<>|&;*?#()".contains(c)) {
+        return arg;
+    }
+
+    // Quoting escapes `"`, `` ` ``, `$` and `\`; the string escape then doubles every `\`
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn autostart_entry_only_starts_in_the_background() {
+        let entry = render_autostart().unwrap();
+        let exec = entry
+            .lines()
+            .find(|line| line.starts_with("Exec="))
+            .unwrap();
+        assert!(exec.ends_with(" --background"), "{}", exec);
+        assert!(!entry.contains("%u"));
+        assert!(!entry.contains("MimeType"));
+
+        let entry = render().unwrap();
+        assert!(entry.contains(" %u\n"));
+        assert!(entry.contains("MimeType=x-scheme-handler/dashtext;"));
+    }
+
+    #[test]
+    fn quotes_exec_arguments() {
+        assert_eq!(quote("/usr/bin/dashtext"), "/usr/bin/dashtext");
+        assert_eq!(
+            quote("/opt/Dash Text/dashtext"),
+            "\"/opt/Dash Text/dashtext\""
+        );
+        assert_eq!(quote("/tmp/100%"), "/tmp/100%%");
+    }
+}
@@ -0,0 +1,205 @@
+//! Diagnostics bundles for bug reports.
+//!
+//! A bundle is a single tar.gz holding what's usually needed to debug a
+//! problem: version and platform, hotkey backend status, applied migrations,
+//! settings and sync state with secrets redacted, log files and crash
+//! reports. Draft content is never included.
+
+use crate::{crash, db, hotkey, logs, paths, profiles, updater};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Map, Value};
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::{Column, Row};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// Top-level directory inside the archive
+const ROOT: &str = "dashtext-diagnostics";
+
+/// Tables dumped into the bundle, each a single row
+const TABLES: &[&str] = &["settings", "sync_state"];
+
+/// Columns whose names contain any of these are redacted
+const SECRET_MARKERS: &[&str] = &["token", "secret", "password"];
+
+const REDACTED: &str = "<redacted>";
+
+/// Write a bundle into `directory`, or the downloads folder, returning its path
+pub async fn create_bundle(app: &AppHandle, directory: Option<&str>) -> Result<PathBuf, String> {
+    let directory = match directory {
+        Some(directory) => PathBuf::from(directory),
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| "Could not determine downloads directory".to_string())?,
+    };
+    let stamp = OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year][month][day]-[hour][minute][second]"
+        ))
+        .map_err(|e| e.to_string())?;
+    let path = directory.join(format!("{}-{}.tar.gz", ROOT, stamp));
+
+    let summary = collect_summary(app).await;
+    let summary = serde_json::to_vec_pretty(&summary)
+        .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+
+    if let Err(e) = write_archive(&path, &summary) {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+    tracing::info!("Wrote diagnostics bundle {:?}", path);
+    Ok(path)
+}
+
+/// Everything that isn't a file copied as-is; failures are recorded, not fatal
+async fn collect_summary(app: &AppHandle) -> Value {
+    let mut summary = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "platform": updater::get_platform_key(),
+        "profile": profiles::current(),
+        "portable": paths::is_portable(),
+        "hotkeys": hotkey::backend_status(),
+        "log_levels": logs::levels(),
+        "known_migrations": db::known_migration_versions(),
+    });
+
+    match db::pool(app).await {
+        Ok(pool) => {
+            summary["applied_migrations"] = db::applied_migrations(&pool)
+                .await
+                .map_or_else(|e| json!({ "error": e }), |m| json!(m));
+            for table in TABLES {
+                summary[*table] = dump_table(&pool, table)
+                    .await
+                    .unwrap_or_else(|e| json!({ "error": e }));
+            }
+        }
+        Err(e) => summary["database"] = json!({ "error": e }),
+    }
+
+    summary
+}
+
+/// Rows of a table as JSON objects, with secret columns redacted
+async fn dump_table(pool: &SqlitePool, table: &str) -> Result<Value, String> {
+    let rows = sqlx::query(&format!("SELECT * FROM {}", table))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+    Ok(Value::Array(rows.iter().map(row_to_json).collect()))
+}
+
+fn row_to_json(row: &SqliteRow) -> Value {
+    let mut object = Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let name = column.name();
+        let value = if let Ok(value) = row.try_get::<Option<String>, _>(i) {
+            json!(value)
+        } else if let Ok(value) = row.try_get::<Option<i64>, _>(i) {
+            json!(value)
+        } else if let Ok(value) = row.try_get::<Option<f64>, _>(i) {
+            json!(value)
+        } else {
+            json!("<binary>")
+        };
+
+        let secret = SECRET_MARKERS.iter().any(|marker| name.contains(marker));
+        let value = if secret && !value.is_null() {
+            json!(REDACTED)
+        } else {
+            value
+        };
+        object.insert(name.to_string(), value);
+    }
+    Value::Object(object)
+}
+
+fn write_archive(path: &Path, summary: &[u8]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(summary.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(OffsetDateTime::now_utc().unix_timestamp().max(0) as u64);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, format!("{}/summary.json", ROOT), summary)
+        .map_err(|e| format!("Failed to write summary: {}", e))?;
+
+    if let Ok(dir) = paths::log_dir() {
+        append_dir(&mut archive, &dir, "logs")?;
+    }
+    if let Ok(dir) = crash::crash_dir() {
+        append_dir(&mut archive, &dir, "crashes")?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map(|_| ())
+        .map_err(|e| format!("Failed to finish {:?}: {}", path, e))
+}
+
+/// Add the regular files directly inside `dir`, skipping it if missing
+fn append_dir(
+    archive: &mut tar::Builder<GzEncoder<File>>,
+    dir: &Path,
+    name: &str,
+) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name();
+        let archive_path = Path::new(ROOT).join(name).join(&file_name);
+        archive
+            .append_path_with_name(&path, archive_path)
+            .map_err(|e| format!("Failed to add {:?}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn redacts_secret_columns() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let row = sqlx::query(
+                "SELECT 'abc' AS access_token, 's3cret' AS client_secret, 'hunter2' AS password,
+                        NULL AS refresh_token, 'dark' AS theme, 3 AS count, 1.5 AS ratio,
+                        x'00ff' AS data",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+            assert_eq!(
+                row_to_json(&row),
+                json!({
+                    "access_token": REDACTED,
+                    "client_secret": REDACTED,
+                    "password": REDACTED,
+                    "refresh_token": null,
+                    "theme": "dark",
+                    "count": 3,
+                    "ratio": 1.5,
+                    "data": "<binary>",
+                })
+            );
+        });
+    }
+}
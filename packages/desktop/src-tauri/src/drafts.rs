@@ -0,0 +1,526 @@
+//! Native access to the `draft` table.
+//!
+//! All draft CRUD goes through here: the frontend through the commands in
+//! [`commands`], and the CLI, tray, sockets and silent capture directly.
+//! Every write should be followed by [`notify`] so open windows can refresh
+//! their draft lists.
+
+pub mod commands;
+pub mod revisions;
+pub mod search;
+pub mod trash;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Window};
+use time::{macros::format_description, OffsetDateTime};
+
+/// Event emitted when a draft is changed from Rust
+pub const CHANGED_EVENT: &str = "drafts:changed";
+
+/// A row from the `draft` table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Draft {
+    pub uuid: String,
+    pub content: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub deleted_at: Option<String>,
+    pub archived: bool,
+    pub pinned: bool,
+}
+
+impl Draft {
+    /// First line of the content, as shown in the sidebar
+    pub fn title(&self) -> &str {
+        let first_line = self.content.lines().next().unwrap_or("").trim();
+        if first_line.is_empty() {
+            "Untitled"
+        } else {
+            first_line
+        }
+    }
+}
+
+/// Which drafts a listing includes besides active ones
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListFilter {
+    pub archived: bool,
+    pub deleted: bool,
+}
+
+/// What happened to a draft in a [`CHANGED_EVENT`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    /// Removed for good, not just moved to the trash
+    Deleted,
+}
+
+/// Result of [`pin`]
+#[derive(Debug, Clone, Serialize)]
+pub struct PinChange {
+    pub draft: Draft,
+    /// Draft that was pinned before, which may be the same one
+    pub previous: Option<String>,
+}
+
+impl PinChange {
+    /// Another draft that lost its pin, if any
+    pub fn unpinned(&self) -> Option<&str> {
+        self.previous
+            .as_deref()
+            .filter(|previous| *previous != self.draft.uuid)
+    }
+}
+
+/// Payload of [`CHANGED_EVENT`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftChange {
+    pub uuid: String,
+    pub kind: ChangeKind,
+    /// Label of the window whose command made the change, which already shows it
+    pub source: Option<String>,
+}
+
+/// Current UTC time in the same format as `Date.toISOString()` on the frontend
+pub fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
+        ))
+        .expect("UTC timestamps are always formattable")
+}
+
+/// Tell every window that a draft changed
+pub fn notify<R: Runtime>(app: &AppHandle<R>, uuid: &str, kind: ChangeKind) {
+    emit_change(app, uuid, kind, None);
+}
+
+/// Tell every window that a draft changed through a command from `window`
+pub fn notify_from<R: Runtime>(window: &Window<R>, uuid: &str, kind: ChangeKind) {
+    emit_change(window.app_handle(), uuid, kind, Some(window.label()));
+}
+
+fn emit_change<R: Runtime>(
+    app: &AppHandle<R>,
+    uuid: &str,
+    kind: ChangeKind,
+    source: Option<&str>,
+) {
+    let change = DraftChange {
+        uuid: uuid.to_string(),
+        kind,
+        source: source.map(str::to_string),
+    };
+    if let Err(e) = app.emit(CHANGED_EVENT, change) {
+        tracing::warn!("Failed to emit {}: {}", CHANGED_EVENT, e);
+    }
+}
+
+/// Get a draft by UUID
+pub async fn get(pool: &SqlitePool, uuid: &str) -> Result<Option<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE uuid = ?",
+    )
+    .bind(uuid)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load draft {}: {}", uuid, e))
+}
+
+/// Get the pinned draft, if any
+pub async fn pinned(pool: &SqlitePool) -> Result<Option<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE pinned = 1 AND deleted_at IS NULL
+         ORDER BY modified_at DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load pinned draft: {}", e))
+}
+
+/// List drafts, most recently modified first
+pub async fn list(pool: &SqlitePool, filter: ListFilter) -> Result<Vec<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE (? OR archived = 0) AND (? OR deleted_at IS NULL)
+         ORDER BY modified_at DESC",
+    )
+    .bind(filter.archived)
+    .bind(filter.deleted)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list drafts: {}", e))
+}
+
+/// The `limit` most recently modified active drafts
+pub async fn recent(pool: &SqlitePool, limit: u32) -> Result<Vec<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE archived = 0 AND deleted_at IS NULL
+         ORDER BY modified_at DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list recent drafts: {}", e))
+}
+
+/// List drafts containing `query` (case-insensitive), most recently modified first
+pub async fn search(
+    pool: &SqlitePool,
+    query: &str,
+    filter: ListFilter,
+) -> Result<Vec<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE (? OR archived = 0) AND (? OR deleted_at IS NULL)
+         AND content LIKE ? ESCAPE '\\'
+         ORDER BY modified_at DESC",
+    )
+    .bind(filter.archived)
+    .bind(filter.deleted)
+    .bind(format!("%{}%", escape_like(query)))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to search drafts: {}", e))
+}
+
+/// List active (not archived or deleted) drafts whose content starts with `prefix`
+pub async fn list_starting_with(pool: &SqlitePool, prefix: &str) -> Result<Vec<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE deleted_at IS NULL AND archived = 0 AND content LIKE ? ESCAPE '\\'
+         ORDER BY modified_at DESC",
+    )
+    .bind(format!("{}%", escape_like(prefix)))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list drafts: {}", e))
+}
+
+/// Escape LIKE wildcards so `text` matches literally (with `ESCAPE '\'`)
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Create a new draft with the given content
+pub async fn create(pool: &SqlitePool, content: &str) -> Result<Draft, String> {
+    let now = now();
+    sqlx::query_as::<_, Draft>(
+        "INSERT INTO draft (uuid, content, created_at, modified_at, archived, pinned)
+         VALUES (?, ?, ?, ?, 0, 0)
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(content)
+    .bind(&now)
+    .bind(&now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to create draft: {}", e))
+}
+
+/// Replace a draft's content, recording a revision
+pub async fn save(pool: &SqlitePool, uuid: &str, content: &str) -> Result<Draft, String> {
+    write(pool, uuid, content, true).await
+}
+
+/// Replace a draft's content, adding to its history in the same transaction
+///
+/// With `coalesce`, the change may be folded into the newest revision.
+async fn write(
+    pool: &SqlitePool,
+    uuid: &str,
+    content: &str,
+    coalesce: bool,
+) -> Result<Draft, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let old: String = sqlx::query_scalar("SELECT content FROM draft WHERE uuid = ?")
+        .bind(uuid)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load draft {}: {}", uuid, e))?
+        .ok_or_else(|| format!("Draft not found: {}", uuid))?;
+
+    let draft = sqlx::query_as::<_, Draft>(
+        "UPDATE draft SET content = ?, modified_at = ? WHERE uuid = ?
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+    )
+    .bind(content)
+    .bind(now())
+    .bind(uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save draft {}: {}", uuid, e))?;
+
+    revisions::record(&mut tx, uuid, &old, content, coalesce).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(draft)
+}
+
+/// Append a block of text to the end of a draft, separated by a blank line
+///
+/// Drafts in the trash are rejected, so text never lands where nobody will see it.
+pub async fn append(pool: &SqlitePool, uuid: &str, text: &str) -> Result<Draft, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let existing: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT content, deleted_at FROM draft WHERE uuid = ?")
+            .bind(uuid)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load draft {}: {}", uuid, e))?;
+    let existing = match existing {
+        Some((content, None)) => content,
+        Some((_, Some(_))) => return Err(format!("Draft is in the trash: {}", uuid)),
+        None => return Err(format!("Draft not found: {}", uuid)),
+    };
+
+    let content = if existing.trim().is_empty() {
+        text.to_string()
+    } else {
+        format!("{}\n\n{}", existing.trim_end(), text)
+    };
+
+    let draft = sqlx::query_as::<_, Draft>(
+        "UPDATE draft SET content = ?, modified_at = ? WHERE uuid = ?
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+    )
+    .bind(&content)
+    .bind(now())
+    .bind(uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update draft {}: {}", uuid, e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(draft)
+}
+
+/// Pin a draft, unpinning any other draft in the same transaction
+///
+/// The `idx_draft_single_pin` index rejects a second pinned draft, so the
+/// previous one has to be unpinned first.
+pub async fn pin(pool: &SqlitePool, uuid: &str) -> Result<PinChange, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let previous: Option<String> = sqlx::query_scalar("SELECT uuid FROM draft WHERE pinned = 1")
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load pinned draft: {}", e))?;
+
+    sqlx::query("UPDATE draft SET pinned = 0 WHERE pinned = 1 AND uuid != ?")
+        .bind(uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to unpin drafts: {}", e))?;
+
+    let draft = sqlx::query_as::<_, Draft>(
+        "UPDATE draft SET pinned = 1 WHERE uuid = ?
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+    )
+    .bind(uuid)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to pin draft {}: {}", uuid, e))?
+    .ok_or_else(|| format!("Draft not found: {}", uuid))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(PinChange { draft, previous })
+}
+
+/// Unpin a draft
+pub async fn unpin(pool: &SqlitePool, uuid: &str) -> Result<Draft, String> {
+    update_state(pool, uuid, "pinned = 0").await
+}
+
+/// Archive a draft
+pub async fn archive(pool: &SqlitePool, uuid: &str) -> Result<Draft, String> {
+    update_state(pool, uuid, "archived = 1").await
+}
+
+/// Unarchive a draft
+pub async fn unarchive(pool: &SqlitePool, uuid: &str) -> Result<Draft, String> {
+    update_state(pool, uuid, "archived = 0").await
+}
+
+/// Move a draft to the trash
+pub async fn delete(pool: &SqlitePool, uuid: &str) -> Result<Draft, String> {
+    sqlx::query_as::<_, Draft>(
+        "UPDATE draft SET deleted_at = ? WHERE uuid = ?
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+    )
+    .bind(now())
+    .bind(uuid)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to delete draft {}: {}", uuid, e))?
+    .ok_or_else(|| format!("Draft not found: {}", uuid))
+}
+
+/// Restore a draft from the trash
+pub async fn restore(pool: &SqlitePool, uuid: &str) -> Result<Draft, String> {
+    update_state(pool, uuid, "deleted_at = NULL").await
+}
+
+/// Delete a draft for good, along with its revisions and Automerge data
+pub async fn hard_delete(pool: &SqlitePool, uuid: &str) -> Result<(), String> {
+    let report = trash::purge(pool, trash::Selection::Draft(uuid)).await?;
+    if report.drafts.is_empty() {
+        return Err(format!("Draft not found: {}", uuid));
+    }
+    Ok(())
+}
+
+/// Apply a fixed `SET` clause to one draft
+async fn update_state(pool: &SqlitePool, uuid: &str, set: &'static str) -> Result<Draft, String> {
+    sqlx::query_as::<_, Draft>(&format!(
+        "UPDATE draft SET {} WHERE uuid = ?
+         RETURNING uuid, content, created_at, modified_at, deleted_at, archived, pinned",
+        set
+    ))
+    .bind(uuid)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to update draft {}: {}", uuid, e))?
+    .ok_or_else(|| format!("Draft not found: {}", uuid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn append_separates_blocks() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = create(&pool, "").await.unwrap();
+
+            let draft = append(&pool, &draft.uuid, "one").await.unwrap();
+            assert_eq!(draft.content, "one");
+            let draft = append(&pool, &draft.uuid, "two").await.unwrap();
+            assert_eq!(draft.content, "one\n\ntwo");
+        });
+    }
+
+    #[test]
+    fn append_rejects_trashed_and_missing_drafts() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = create(&pool, "kept").await.unwrap();
+            delete(&pool, &draft.uuid).await.unwrap();
+
+            let err = append(&pool, &draft.uuid, "lost").await.unwrap_err();
+            assert!(err.contains("trash"), "{}", err);
+            let draft = get(&pool, &draft.uuid).await.unwrap().unwrap();
+            assert_eq!(draft.content, "kept");
+
+            assert!(append(&pool, "missing", "lost").await.is_err());
+        });
+    }
+
+    #[test]
+    fn pinning_moves_the_pin() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let first = create(&pool, "first").await.unwrap();
+            let second = create(&pool, "second").await.unwrap();
+
+            let change = pin(&pool, &first.uuid).await.unwrap();
+            assert!(change.draft.pinned);
+            assert_eq!(change.previous, None);
+            assert_eq!(change.unpinned(), None);
+
+            // Pinning the same draft again isn't an unpin
+            let change = pin(&pool, &first.uuid).await.unwrap();
+            assert_eq!(change.previous.as_deref(), Some(first.uuid.as_str()));
+            assert_eq!(change.unpinned(), None);
+
+            let change = pin(&pool, &second.uuid).await.unwrap();
+            assert_eq!(change.unpinned(), Some(first.uuid.as_str()));
+            assert!(!get(&pool, &first.uuid).await.unwrap().unwrap().pinned);
+            assert_eq!(pinned(&pool).await.unwrap().unwrap().uuid, second.uuid);
+
+            let draft = unpin(&pool, &second.uuid).await.unwrap();
+            assert!(!draft.pinned);
+            assert!(pinned(&pool).await.unwrap().is_none());
+
+            assert!(pin(&pool, "missing").await.is_err());
+        });
+    }
+
+    #[test]
+    fn index_rejects_a_second_pinned_draft() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let first = create(&pool, "first").await.unwrap();
+            let second = create(&pool, "second").await.unwrap();
+            pin(&pool, &first.uuid).await.unwrap();
+
+            let result = sqlx::query("UPDATE draft SET pinned = 1 WHERE uuid = ?")
+                .bind(&second.uuid)
+                .execute(&pool)
+                .await;
+            let err = result.unwrap_err().to_string();
+            assert!(err.contains("UNIQUE"), "{}", err);
+
+            // Any number of drafts can be unpinned
+            unpin(&pool, &first.uuid).await.unwrap();
+            unpin(&pool, &second.uuid).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn changes_name_the_window_that_made_them() {
+        let app = tauri::test::mock_app();
+        let window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .unwrap();
+
+        let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received = changes.clone();
+        tauri::Listener::listen(&app, CHANGED_EVENT, move |event| {
+            let change: DraftChange = serde_json::from_str(event.payload()).unwrap();
+            received.lock().unwrap().push(change);
+        });
+
+        notify_from(&window.as_ref().window(), "a", ChangeKind::Updated);
+        notify(app.handle(), "b", ChangeKind::Created);
+
+        let changes = changes.lock().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].uuid, "a");
+        assert_eq!(changes[0].source.as_deref(), Some("main"));
+        assert_eq!(changes[1].uuid, "b");
+        assert_eq!(changes[1].source, None);
+    }
+}
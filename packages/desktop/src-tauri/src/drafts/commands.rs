@@ -0,0 +1,205 @@
+//! Draft commands for the frontend's `DraftAPI`.
+//!
+//! Each command works on the current profile's database and announces its
+//! change with [`notify_from`], so every other window stays consistent.
+
+use super::revisions::{self, DiffLine, Revision, RevisionInfo};
+use super::search::{self, SearchFilter, SearchHit};
+use super::trash::{self, PurgeReport, Selection, TrashSettings, TrashState};
+use super::{ChangeKind, Draft, ListFilter, PinChange, notify_from};
+use crate::db;
+use tauri::{AppHandle, State, Window};
+
+/// Drafts that aren't in the trash, archived included, most recently modified first
+#[tauri::command]
+pub async fn list_drafts(app: AppHandle) -> Result<Vec<Draft>, String> {
+    let pool = db::pool(&app).await?;
+    let filter = ListFilter {
+        archived: true,
+        deleted: false,
+    };
+    super::list(&pool, filter).await
+}
+
+/// Get a draft by UUID
+#[tauri::command]
+pub async fn get_draft(app: AppHandle, uuid: String) -> Result<Option<Draft>, String> {
+    let pool = db::pool(&app).await?;
+    super::get(&pool, &uuid).await
+}
+
+/// Full-text search, best matches first
+#[tauri::command]
+pub async fn search_drafts(
+    app: AppHandle,
+    query: String,
+    filter: Option<SearchFilter>,
+    limit: Option<u32>,
+) -> Result<Vec<SearchHit>, String> {
+    let pool = db::pool(&app).await?;
+    let filter = filter.unwrap_or_default();
+    let limit = limit.unwrap_or(search::DEFAULT_LIMIT);
+    search::search(&pool, &query, filter, limit).await
+}
+
+/// Create an empty draft
+#[tauri::command]
+pub async fn create_draft(app: AppHandle, window: Window) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::create(&pool, "").await?;
+    notify_from(&window, &draft.uuid, ChangeKind::Created);
+    Ok(draft)
+}
+
+/// Replace a draft's content
+#[tauri::command]
+pub async fn save_draft(
+    app: AppHandle,
+    window: Window,
+    uuid: String,
+    content: String,
+) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::save(&pool, &uuid, &content).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Archive a draft
+#[tauri::command]
+pub async fn archive_draft(app: AppHandle, window: Window, uuid: String) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::archive(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Unarchive a draft
+#[tauri::command]
+pub async fn unarchive_draft(
+    app: AppHandle,
+    window: Window,
+    uuid: String,
+) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::unarchive(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Pin a draft, unpinning any other, and report which draft was pinned before
+#[tauri::command]
+pub async fn pin_draft(app: AppHandle, window: Window, uuid: String) -> Result<PinChange, String> {
+    let pool = db::pool(&app).await?;
+    let change = super::pin(&pool, &uuid).await?;
+    if let Some(previous) = change.unpinned() {
+        notify_from(&window, previous, ChangeKind::Updated);
+    }
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(change)
+}
+
+/// Unpin a draft
+#[tauri::command]
+pub async fn unpin_draft(app: AppHandle, window: Window, uuid: String) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::unpin(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Move a draft to the trash
+#[tauri::command]
+pub async fn delete_draft(app: AppHandle, window: Window, uuid: String) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    super::delete(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(())
+}
+
+/// Restore a draft from the trash
+#[tauri::command]
+pub async fn restore_draft(app: AppHandle, window: Window, uuid: String) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = super::restore(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Delete a draft for good
+#[tauri::command]
+pub async fn hard_delete_draft(app: AppHandle, window: Window, uuid: String) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    super::hard_delete(&pool, &uuid).await?;
+    notify_from(&window, &uuid, ChangeKind::Deleted);
+    Ok(())
+}
+
+/// A draft's revisions, newest first
+#[tauri::command]
+pub async fn list_revisions(app: AppHandle, uuid: String) -> Result<Vec<RevisionInfo>, String> {
+    let pool = db::pool(&app).await?;
+    revisions::list(&pool, &uuid).await
+}
+
+/// Get a revision with its content
+#[tauri::command]
+pub async fn get_revision(
+    app: AppHandle,
+    uuid: String,
+    id: i64,
+) -> Result<Option<Revision>, String> {
+    let pool = db::pool(&app).await?;
+    revisions::get(&pool, &uuid, id).await
+}
+
+/// Line diff from a revision to the draft's current content
+#[tauri::command]
+pub async fn diff_revision(app: AppHandle, uuid: String, id: i64) -> Result<Vec<DiffLine>, String> {
+    let pool = db::pool(&app).await?;
+    revisions::diff(&pool, &uuid, id).await
+}
+
+/// Restore a draft to a revision
+#[tauri::command]
+pub async fn restore_revision(
+    app: AppHandle,
+    window: Window,
+    uuid: String,
+    id: i64,
+) -> Result<Draft, String> {
+    let pool = db::pool(&app).await?;
+    let draft = revisions::restore(&pool, &uuid, id).await?;
+    notify_from(&window, &uuid, ChangeKind::Updated);
+    Ok(draft)
+}
+
+/// Drafts in the trash, most recently deleted first
+#[tauri::command]
+pub async fn list_trash(app: AppHandle) -> Result<Vec<Draft>, String> {
+    let pool = db::pool(&app).await?;
+    trash::list(&pool).await
+}
+
+/// Delete everything in the trash for good
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle, window: Window) -> Result<PurgeReport, String> {
+    let pool = db::pool(&app).await?;
+    let report = trash::purge(&pool, Selection::All).await?;
+    for uuid in &report.drafts {
+        notify_from(&window, uuid, ChangeKind::Deleted);
+    }
+    Ok(report)
+}
+
+/// How long drafts stay in the trash
+#[tauri::command]
+pub fn get_trash_settings(state: State<'_, TrashState>) -> TrashSettings {
+    state.settings()
+}
+
+/// Change how long drafts stay in the trash; 0 keeps them until emptied
+#[tauri::command]
+pub fn set_trash_retention(days: u32, state: State<'_, TrashState>) -> Result<(), String> {
+    state.set_retention_days(days)
+}
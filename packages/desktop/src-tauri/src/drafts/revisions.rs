@@ -0,0 +1,551 @@
+//! Revision history of draft content.
+//!
+//! [`super::save`] records a revision in the same transaction as the write.
+//! Saves within [`BUCKET_SECS`] of a revision starting are folded into it,
+//! unless they remove most of the text, so an accidental delete followed by
+//! autosave always leaves the earlier text recoverable.
+//!
+//! The newest revision of a draft stores its full content; each older one
+//! stores a line delta that rebuilds it from the next newer revision. Pruning
+//! only ever drops the oldest revisions, so deltas never lose their base.
+
+use super::{Draft, now};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, DiffOp, TextDiff};
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// Saves this soon after a revision starts are folded into it
+const BUCKET_SECS: f64 = 300.0;
+
+/// Most revisions kept per draft
+const MAX_REVISIONS: i64 = 100;
+
+/// Revisions last saved longer ago than this are dropped
+const MAX_AGE_DAYS: f64 = 30.0;
+
+/// A revision without its content
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RevisionInfo {
+    pub id: i64,
+    pub created_at: String,
+    /// Last save folded into this revision
+    pub updated_at: String,
+    /// Content length in bytes
+    pub size: i64,
+}
+
+/// A revision with its content rebuilt
+#[derive(Debug, Clone, Serialize)]
+pub struct Revision {
+    #[serde(flatten)]
+    pub info: RevisionInfo,
+    pub content: String,
+}
+
+/// One line of a diff from a revision to the current content
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub tag: LineTag,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineTag {
+    Equal,
+    /// Only in the revision
+    Removed,
+    /// Only in the current content
+    Added,
+}
+
+/// Step of a delta rebuilding a revision from the next newer one
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Op {
+    /// Lines `start..start + len` of the newer text
+    Copy(usize, usize),
+    Insert(String),
+}
+
+/// Newest revision of a draft
+#[derive(sqlx::FromRow)]
+struct Head {
+    id: i64,
+    content: Option<String>,
+    /// Seconds since the revision started
+    age: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredRevision {
+    #[sqlx(flatten)]
+    info: RevisionInfo,
+    content: Option<String>,
+    delta: Option<String>,
+}
+
+/// Record that a draft's content changed from `old` to `new`
+///
+/// With `coalesce`, a save soon after the newest revision started replaces
+/// its content instead of adding a revision.
+pub(super) async fn record(
+    conn: &mut SqliteConnection,
+    uuid: &str,
+    old: &str,
+    new: &str,
+    coalesce: bool,
+) -> Result<(), String> {
+    if old == new {
+        return Ok(());
+    }
+
+    let now = now();
+    let mut head = sqlx::query_as::<_, Head>(
+        "SELECT id, content, (julianday(?) - julianday(created_at)) * 86400 AS age
+         FROM draft_revision WHERE draft_uuid = ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(&now)
+    .bind(uuid)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load revisions of {}: {}", uuid, e))?;
+
+    let in_sync = head
+        .as_ref()
+        .is_some_and(|head| head.content.as_deref() == Some(old));
+    if in_sync && let Some(head) = &head {
+        let removes_most = new.len() * 2 < old.len();
+        if coalesce && head.age < BUCKET_SECS && !removes_most {
+            return replace_head(conn, uuid, head, old, new, &now).await;
+        }
+    } else if !old.is_empty() {
+        // History is missing or another write skipped it, so keep the text being replaced
+        let id = push(conn, uuid, head.as_ref(), old, &now).await?;
+        head = Some(Head {
+            id,
+            content: Some(old.to_string()),
+            age: 0.0,
+        });
+    }
+
+    push(conn, uuid, head.as_ref(), new, &now).await?;
+    prune(conn, uuid).await
+}
+
+/// Replace the newest revision's content from `old` to `new`
+///
+/// The revision before it is a delta against `old`, so it's rebuilt and
+/// re-encoded against `new` first.
+async fn replace_head(
+    conn: &mut SqliteConnection,
+    uuid: &str,
+    head: &Head,
+    old: &str,
+    new: &str,
+    now: &str,
+) -> Result<(), String> {
+    let previous = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT id, delta FROM draft_revision
+         WHERE draft_uuid = ? AND id < ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(uuid)
+    .bind(head.id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load revisions of {}: {}", uuid, e))?;
+
+    if let Some((id, Some(previous_delta))) = previous {
+        let content = apply(old, &previous_delta)?;
+        sqlx::query("UPDATE draft_revision SET delta = ? WHERE id = ?")
+            .bind(delta(new, &content)?)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compress revision of {}: {}", uuid, e))?;
+    }
+
+    sqlx::query("UPDATE draft_revision SET content = ?, size = ?, updated_at = ? WHERE id = ?")
+        .bind(new)
+        .bind(new.len() as i64)
+        .bind(now)
+        .bind(head.id)
+        .execute(&mut *conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to update revision of {}: {}", uuid, e))
+}
+
+/// Add a revision on top of `head`, turning `head` into a delta against it
+async fn push(
+    conn: &mut SqliteConnection,
+    uuid: &str,
+    head: Option<&Head>,
+    content: &str,
+    now: &str,
+) -> Result<i64, String> {
+    if let Some(head) = head
+        && let Some(head_content) = &head.content
+    {
+        sqlx::query("UPDATE draft_revision SET content = NULL, delta = ? WHERE id = ?")
+            .bind(delta(content, head_content)?)
+            .bind(head.id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compress revision of {}: {}", uuid, e))?;
+    }
+
+    sqlx::query_scalar(
+        "INSERT INTO draft_revision (draft_uuid, created_at, updated_at, size, content)
+         VALUES (?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(uuid)
+    .bind(now)
+    .bind(now)
+    .bind(content.len() as i64)
+    .bind(content)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to add revision of {}: {}", uuid, e))
+}
+
+/// Drop a draft's revisions beyond the retention policy
+async fn prune(conn: &mut SqliteConnection, uuid: &str) -> Result<(), String> {
+    sqlx::query(
+        "DELETE FROM draft_revision WHERE draft_uuid = ?1 AND (
+             julianday(?2) - julianday(updated_at) > ?3
+             OR id NOT IN (
+                 SELECT id FROM draft_revision WHERE draft_uuid = ?1 ORDER BY id DESC LIMIT ?4
+             )
+         )",
+    )
+    .bind(uuid)
+    .bind(now())
+    .bind(MAX_AGE_DAYS)
+    .bind(MAX_REVISIONS)
+    .execute(&mut *conn)
+    .await
+    .map(|_| ())
+    .map_err(|e| format!("Failed to prune revisions of {}: {}", uuid, e))
+}
+
+/// Drop every draft's revisions beyond the retention policy
+///
+/// Revisions are pruned as drafts are saved; this catches drafts that
+/// haven't been saved in a while.
+pub async fn prune_all(pool: &SqlitePool) -> Result<u64, String> {
+    sqlx::query(
+        "DELETE FROM draft_revision WHERE julianday(?1) - julianday(updated_at) > ?2
+         OR id IN (
+             SELECT id FROM (
+                 SELECT id, ROW_NUMBER() OVER (PARTITION BY draft_uuid ORDER BY id DESC) AS n
+                 FROM draft_revision
+             ) WHERE n > ?3
+         )",
+    )
+    .bind(now())
+    .bind(MAX_AGE_DAYS)
+    .bind(MAX_REVISIONS)
+    .execute(pool)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(|e| format!("Failed to prune revisions: {}", e))
+}
+
+/// A draft's revisions, newest first
+pub async fn list(pool: &SqlitePool, uuid: &str) -> Result<Vec<RevisionInfo>, String> {
+    sqlx::query_as::<_, RevisionInfo>(
+        "SELECT id, created_at, updated_at, size FROM draft_revision
+         WHERE draft_uuid = ? ORDER BY id DESC",
+    )
+    .bind(uuid)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list revisions of {}: {}", uuid, e))
+}
+
+/// Get a revision with its content
+pub async fn get(pool: &SqlitePool, uuid: &str, id: i64) -> Result<Option<Revision>, String> {
+    let rows = sqlx::query_as::<_, StoredRevision>(
+        "SELECT id, created_at, updated_at, size, content, delta FROM draft_revision
+         WHERE draft_uuid = ? AND id >= ? ORDER BY id DESC",
+    )
+    .bind(uuid)
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load revision {} of {}: {}", id, uuid, e))?;
+
+    // Walk back from the newest revision, applying each delta in turn
+    let mut content: Option<String> = None;
+    for row in &rows {
+        content = Some(match (&row.content, &row.delta, content) {
+            (Some(full), _, _) => full.clone(),
+            (None, Some(delta), Some(newer)) => apply(&newer, delta)?,
+            _ => {
+                return Err(format!(
+                    "Revision {} of {} is missing its content",
+                    row.info.id, uuid
+                ));
+            }
+        });
+    }
+
+    Ok(match (rows.into_iter().last(), content) {
+        (Some(row), Some(content)) if row.info.id == id => Some(Revision {
+            info: row.info,
+            content,
+        }),
+        _ => None,
+    })
+}
+
+/// Line diff from a revision to the draft's current content
+pub async fn diff(pool: &SqlitePool, uuid: &str, id: i64) -> Result<Vec<DiffLine>, String> {
+    let draft = super::get(pool, uuid)
+        .await?
+        .ok_or_else(|| format!("Draft not found: {}", uuid))?;
+    let revision = get(pool, uuid, id)
+        .await?
+        .ok_or_else(|| format!("Revision {} of {} not found", id, uuid))?;
+
+    let diff = TextDiff::from_lines(&revision.content, &draft.content);
+    Ok(diff
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => LineTag::Equal,
+                ChangeTag::Delete => LineTag::Removed,
+                ChangeTag::Insert => LineTag::Added,
+            },
+            text: change.value().to_string(),
+        })
+        .collect())
+}
+
+/// Replace a draft's content with a revision's, keeping the replaced text as a revision
+pub async fn restore(pool: &SqlitePool, uuid: &str, id: i64) -> Result<Draft, String> {
+    let revision = get(pool, uuid, id)
+        .await?
+        .ok_or_else(|| format!("Revision {} of {} not found", id, uuid))?;
+    super::write(pool, uuid, &revision.content, false).await
+}
+
+/// Delta that rebuilds `older` from `newer`
+fn delta(newer: &str, older: &str) -> Result<String, String> {
+    let diff = TextDiff::from_lines(newer, older);
+    let older_lines = diff.new_slices();
+    let ops: Vec<Op> = diff
+        .ops()
+        .iter()
+        .filter_map(|op| match *op {
+            DiffOp::Equal { old_index, len, .. } => Some(Op::Copy(old_index, len)),
+            DiffOp::Delete { .. } => None,
+            DiffOp::Insert {
+                new_index, new_len, ..
+            }
+            | DiffOp::Replace {
+                new_index, new_len, ..
+            } => Some(Op::Insert(
+                older_lines[new_index..new_index + new_len].concat(),
+            )),
+        })
+        .collect();
+    serde_json::to_string(&ops).map_err(|e| format!("Failed to encode revision delta: {}", e))
+}
+
+/// Rebuild an older revision from `newer` and its delta
+fn apply(newer: &str, delta: &str) -> Result<String, String> {
+    let ops: Vec<Op> = serde_json::from_str(delta)
+        .map_err(|e| format!("Failed to decode revision delta: {}", e))?;
+    // Split exactly as `delta` did, which also ends lines at a lone `\r`
+    let diff = TextDiff::from_lines(newer, "");
+    let lines = diff.old_slices();
+
+    let mut text = String::with_capacity(newer.len());
+    for op in ops {
+        match op {
+            Op::Copy(start, len) => {
+                let copied = lines
+                    .get(start..start.saturating_add(len))
+                    .ok_or_else(|| "Revision delta is out of range".to_string())?;
+                text.extend(copied.iter().copied());
+            }
+            Op::Insert(inserted) => text.push_str(&inserted),
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drafts;
+    use crate::testing;
+
+    /// Every revision's content, newest first
+    async fn contents(pool: &SqlitePool, uuid: &str) -> Vec<String> {
+        let mut contents = Vec::new();
+        for info in list(pool, uuid).await.unwrap() {
+            let revision = get(pool, uuid, info.id).await.unwrap().unwrap();
+            assert_eq!(revision.info.size, revision.content.len() as i64);
+            contents.push(revision.content);
+        }
+        contents
+    }
+
+    fn round_trip(newer: &str, older: &str) {
+        let delta = delta(newer, older).unwrap();
+        assert_eq!(apply(newer, &delta).unwrap(), older, "delta {}", delta);
+    }
+
+    #[test]
+    fn rebuilds_older_text() {
+        round_trip("a\nx\nc\n", "a\nb\nc\n");
+        round_trip("keep\nnew line\n", "keep\n");
+    }
+
+    #[test]
+    fn handles_line_endings() {
+        round_trip("one\r\ntwo\r\n", "zero\r\none\r\ntwo");
+        round_trip("old mac\rtext\rhere", "old mac\rchanged\rhere\r");
+        round_trip("mixed\r\nends\rand\n", "mixed\nends\r\nand");
+    }
+
+    #[test]
+    fn handles_missing_final_newline() {
+        round_trip("last line", "last line\n");
+        round_trip("a\nb\n", "a\nb");
+    }
+
+    #[test]
+    fn handles_empty_text() {
+        round_trip("", "something\n");
+        round_trip("something\n", "");
+        round_trip("", "");
+    }
+
+    #[test]
+    fn rejects_out_of_range_delta() {
+        assert!(apply("one line", r#"[{"copy":[3,1]}]"#).is_err());
+    }
+
+    #[test]
+    fn records_and_rebuilds_each_revision() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "").await.unwrap();
+
+            for content in ["a\n", "a\nb\n", "x\nb\n"] {
+                drafts::write(&pool, &draft.uuid, content, false)
+                    .await
+                    .unwrap();
+            }
+            // Unchanged content adds nothing
+            drafts::write(&pool, &draft.uuid, "x\nb\n", false)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                contents(&pool, &draft.uuid).await,
+                ["x\nb\n", "a\nb\n", "a\n"]
+            );
+        });
+    }
+
+    #[test]
+    fn coalescing_keeps_older_revisions_intact() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "").await.unwrap();
+
+            drafts::write(&pool, &draft.uuid, "a\n", false)
+                .await
+                .unwrap();
+            drafts::write(&pool, &draft.uuid, "a\nb\n", false)
+                .await
+                .unwrap();
+            drafts::save(&pool, &draft.uuid, "x\n").await.unwrap();
+            drafts::save(&pool, &draft.uuid, "x\ny\n").await.unwrap();
+
+            assert_eq!(contents(&pool, &draft.uuid).await, ["x\ny\n", "a\n"]);
+        });
+    }
+
+    #[test]
+    fn large_deletions_start_a_new_revision() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "").await.unwrap();
+
+            drafts::save(&pool, &draft.uuid, "a long paragraph\n")
+                .await
+                .unwrap();
+            drafts::save(&pool, &draft.uuid, "a\n").await.unwrap();
+
+            assert_eq!(
+                contents(&pool, &draft.uuid).await,
+                ["a\n", "a long paragraph\n"]
+            );
+        });
+    }
+
+    #[test]
+    fn restore_keeps_the_replaced_text() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "").await.unwrap();
+            drafts::write(&pool, &draft.uuid, "first\n", false)
+                .await
+                .unwrap();
+            drafts::write(&pool, &draft.uuid, "second\n", false)
+                .await
+                .unwrap();
+
+            let first = list(&pool, &draft.uuid).await.unwrap().pop().unwrap();
+            let restored = restore(&pool, &draft.uuid, first.id).await.unwrap();
+            assert_eq!(restored.content, "first\n");
+            assert_eq!(
+                contents(&pool, &draft.uuid).await,
+                ["first\n", "second\n", "first\n"]
+            );
+
+            assert!(
+                get(&pool, &draft.uuid, first.id + 100)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+            assert!(restore(&pool, &draft.uuid, first.id + 100).await.is_err());
+        });
+    }
+
+    #[test]
+    fn prunes_beyond_the_limit_and_age() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "").await.unwrap();
+            for n in 0..MAX_REVISIONS + 5 {
+                let content = format!("line {}\n", n);
+                drafts::write(&pool, &draft.uuid, &content, false)
+                    .await
+                    .unwrap();
+            }
+
+            let kept = contents(&pool, &draft.uuid).await;
+            assert_eq!(kept.len() as i64, MAX_REVISIONS);
+            assert_eq!(
+                kept.first().unwrap(),
+                &format!("line {}\n", MAX_REVISIONS + 4)
+            );
+            assert_eq!(kept.last().unwrap(), "line 5\n");
+
+            sqlx::query("UPDATE draft_revision SET updated_at = '2000-01-01T00:00:00.000Z'")
+                .execute(&pool)
+                .await
+                .unwrap();
+            assert_eq!(prune_all(&pool).await.unwrap() as i64, MAX_REVISIONS);
+            assert!(list(&pool, &draft.uuid).await.unwrap().is_empty());
+        });
+    }
+}
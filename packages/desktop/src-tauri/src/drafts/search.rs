@@ -0,0 +1,387 @@
+//! Full-text search over draft content.
+//!
+//! Uses the `draft_fts` FTS5 table, which triggers keep in sync with `draft`.
+//! Results are ranked by bm25 and carry a highlighted snippet plus the offset
+//! and line of every match, so the editor can jump straight to it. Offsets
+//! count UTF-16 code units, like JavaScript string indices.
+
+use super::Draft;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// Results returned when no limit is given
+pub const DEFAULT_LIMIT: u32 = 50;
+
+/// Words of context around the best match in a snippet
+const SNIPPET_TOKENS: u32 = 16;
+
+/// Marks the start and end of each match in FTS5 output; never typed by users
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// Which drafts a search includes besides active ones
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    pub archived: bool,
+    pub deleted: bool,
+    /// Only the pinned draft
+    pub pinned: bool,
+}
+
+/// A matched range of text
+#[derive(Debug, Clone, Serialize)]
+pub struct Match {
+    /// 1-based line of the match start
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Best-matching excerpt of a draft
+#[derive(Debug, Clone, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    /// Matches within `text`
+    pub highlights: Vec<Match>,
+}
+
+/// A draft matching a search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub draft: Draft,
+    /// bm25 score; lower is a better match
+    pub rank: f64,
+    pub snippet: Snippet,
+    /// Every match within the draft's content
+    pub matches: Vec<Match>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    draft: Draft,
+    rank: f64,
+    snippet: String,
+    highlighted: String,
+}
+
+/// Search drafts, best matches first
+///
+/// Words must all appear, in any order. `"quoted text"` matches a phrase and
+/// `word*` matches any word starting with `word`.
+pub async fn search(
+    pool: &SqlitePool,
+    query: &str,
+    filter: SearchFilter,
+    limit: u32,
+) -> Result<Vec<SearchHit>, String> {
+    let query = fts_query(query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let start = MATCH_START.to_string();
+    let end = MATCH_END.to_string();
+    let rows = sqlx::query_as::<_, SearchRow>(
+        "SELECT d.uuid, d.content, d.created_at, d.modified_at, d.deleted_at, d.archived,
+                d.pinned, bm25(draft_fts) AS rank,
+                snippet(draft_fts, 0, ?1, ?2, '…', ?3) AS snippet,
+                highlight(draft_fts, 0, ?1, ?2) AS highlighted
+         FROM draft_fts JOIN draft d ON d.id = draft_fts.rowid
+         WHERE draft_fts MATCH ?4 AND (?5 OR d.archived = 0)
+         AND (?6 OR d.deleted_at IS NULL) AND (NOT ?7 OR d.pinned = 1)
+         ORDER BY rank LIMIT ?8",
+    )
+    .bind(&start)
+    .bind(&end)
+    .bind(SNIPPET_TOKENS)
+    .bind(&query)
+    .bind(filter.archived)
+    .bind(filter.deleted)
+    .bind(filter.pinned)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to search drafts: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let (text, highlights) = parse_marked(&row.snippet);
+            let (_, matches) = parse_marked(&row.highlighted);
+            SearchHit {
+                draft: row.draft,
+                rank: row.rank,
+                snippet: Snippet { text, highlights },
+                matches,
+            }
+        })
+        .collect())
+}
+
+/// Turn user input into an FTS5 query
+///
+/// Every word or phrase is quoted, so punctuation in the input is searched
+/// for rather than parsed as FTS5 syntax.
+fn fts_query(input: &str) -> String {
+    let mut terms = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+            push_term(&mut terms, phrase, after.starts_with('*'));
+            rest = after.trim_start_matches('*');
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '"')
+                .unwrap_or(rest.len());
+            let (word, after) = rest.split_at(end);
+            push_term(&mut terms, word, word.ends_with('*'));
+            rest = after;
+        }
+        rest = rest.trim_start();
+    }
+
+    terms.join(" ")
+}
+
+fn push_term(terms: &mut Vec<String>, text: &str, prefix: bool) {
+    let text = text.trim().trim_end_matches('*');
+    if text.is_empty() {
+        return;
+    }
+    let quoted = format!("\"{}\"", text.replace('"', "\"\""));
+    terms.push(if prefix { quoted + "*" } else { quoted });
+}
+
+/// Strip match markers from FTS5 output, returning the plain text and matches
+fn parse_marked(marked: &str) -> (String, Vec<Match>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut matches = Vec::new();
+    let mut offset = 0;
+    let mut line = 1;
+    let mut open = None;
+
+    for c in marked.chars() {
+        match c {
+            MATCH_START => open = Some((line, offset)),
+            MATCH_END => {
+                if let Some((line, start)) = open.take() {
+                    matches.push(Match {
+                        line,
+                        start,
+                        end: offset,
+                    });
+                }
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                offset += c.len_utf16();
+                text.push(c);
+            }
+        }
+    }
+
+    (text, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drafts;
+    use crate::testing;
+
+    /// UUIDs of the drafts matching `query`, best first
+    async fn found(pool: &SqlitePool, query: &str, filter: SearchFilter) -> Vec<String> {
+        search(pool, query, filter, DEFAULT_LIMIT)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.draft.uuid)
+            .collect()
+    }
+
+    #[test]
+    fn quotes_each_word() {
+        assert_eq!(fts_query("hello  world"), r#""hello" "world""#);
+    }
+
+    #[test]
+    fn keeps_phrases_and_prefixes() {
+        assert_eq!(
+            fts_query(r#""exact phrase" pre* "new york"*"#),
+            r#""exact phrase" "pre"* "new york"*"#
+        );
+    }
+
+    #[test]
+    fn treats_syntax_as_text() {
+        assert_eq!(fts_query("a-b OR c:d"), r#""a-b" "OR" "c:d""#);
+        assert_eq!(fts_query(r#"say "hi"#), r#""say" "hi""#);
+    }
+
+    #[test]
+    fn skips_empty_terms() {
+        assert_eq!(fts_query(r#"  * "" "#), "");
+    }
+
+    #[test]
+    fn strips_markers_with_utf16_offsets() {
+        let (text, matches) = parse_marked("a\u{2}b\u{3}\n😀 \u{2}dé\u{3}");
+        assert_eq!(text, "ab\n😀 dé");
+        let ranges: Vec<_> = matches.iter().map(|m| (m.line, m.start, m.end)).collect();
+        assert_eq!(ranges, [(1, 1, 2), (2, 6, 8)]);
+    }
+
+    #[test]
+    fn triggers_keep_the_index_in_sync() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "grocery list").await.unwrap();
+            let all = SearchFilter::default();
+            assert_eq!(found(&pool, "grocery", all).await, [draft.uuid.as_str()]);
+
+            drafts::save(&pool, &draft.uuid, "meeting notes")
+                .await
+                .unwrap();
+            assert!(found(&pool, "grocery", all).await.is_empty());
+            assert_eq!(found(&pool, "meeting", all).await, [draft.uuid.as_str()]);
+
+            drafts::hard_delete(&pool, &draft.uuid).await.unwrap();
+            assert!(found(&pool, "meeting", all).await.is_empty());
+        });
+    }
+
+    #[test]
+    fn matches_phrases_prefixes_and_diacritics() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let phrase = drafts::create(&pool, "the quick brown fox").await.unwrap();
+            let apart = drafts::create(&pool, "brown bears are quick")
+                .await
+                .unwrap();
+            let all = SearchFilter::default();
+
+            assert_eq!(
+                found(&pool, r#""quick brown""#, all).await,
+                [phrase.uuid.as_str()]
+            );
+            assert_eq!(found(&pool, "quick brown", all).await.len(), 2);
+            assert_eq!(found(&pool, "bea*", all).await, [apart.uuid.as_str()]);
+            assert!(found(&pool, "bea", all).await.is_empty());
+
+            let accented = drafts::create(&pool, "Café résumé").await.unwrap();
+            assert_eq!(
+                found(&pool, "cafe resume", all).await,
+                [accented.uuid.as_str()]
+            );
+            // FTS5 syntax in the input is searched for, not parsed
+            assert!(found(&pool, "quick NOT", all).await.is_empty());
+            assert!(found(&pool, "*", all).await.is_empty());
+        });
+    }
+
+    #[test]
+    fn ranks_better_matches_first() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let passing = drafts::create(&pool, &format!("rust {}", "filler ".repeat(50)))
+                .await
+                .unwrap();
+            let focused = drafts::create(&pool, "rust rust rust").await.unwrap();
+
+            let hits = search(&pool, "rust", SearchFilter::default(), DEFAULT_LIMIT)
+                .await
+                .unwrap();
+            let uuids: Vec<&str> = hits.iter().map(|hit| hit.draft.uuid.as_str()).collect();
+            assert_eq!(uuids, [focused.uuid.as_str(), passing.uuid.as_str()]);
+            assert!(hits[0].rank < hits[1].rank);
+
+            let limited = search(&pool, "rust", SearchFilter::default(), 1)
+                .await
+                .unwrap();
+            assert_eq!(limited.len(), 1);
+        });
+    }
+
+    #[test]
+    fn reports_snippets_and_match_offsets() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            drafts::create(&pool, "first line\nsecond needle here\nneedle again")
+                .await
+                .unwrap();
+
+            let hits = search(&pool, "needle", SearchFilter::default(), DEFAULT_LIMIT)
+                .await
+                .unwrap();
+            let hit = &hits[0];
+            let ranges: Vec<_> = hit
+                .matches
+                .iter()
+                .map(|m| (m.line, m.start, m.end))
+                .collect();
+            assert_eq!(ranges, [(2, 18, 24), (3, 30, 36)]);
+            assert!(!hit.snippet.text.contains(MATCH_START));
+            for highlight in &hit.snippet.highlights {
+                let text: String = hit
+                    .snippet
+                    .text
+                    .encode_utf16()
+                    .skip(highlight.start)
+                    .take(highlight.end - highlight.start)
+                    .map(|unit| char::from_u32(unit.into()).unwrap())
+                    .collect();
+                assert_eq!(text, "needle");
+            }
+        });
+    }
+
+    #[test]
+    fn filters_archived_deleted_and_pinned() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let active = drafts::create(&pool, "topic active").await.unwrap();
+            let archived = drafts::create(&pool, "topic archived").await.unwrap();
+            let deleted = drafts::create(&pool, "topic deleted").await.unwrap();
+            let pinned = drafts::create(&pool, "topic pinned").await.unwrap();
+            drafts::archive(&pool, &archived.uuid).await.unwrap();
+            drafts::delete(&pool, &deleted.uuid).await.unwrap();
+            drafts::pin(&pool, &pinned.uuid).await.unwrap();
+
+            let sorted = |mut uuids: Vec<String>| {
+                uuids.sort();
+                uuids
+            };
+            let expect = |drafts: &[&Draft]| {
+                let mut uuids: Vec<String> = drafts.iter().map(|d| d.uuid.clone()).collect();
+                uuids.sort();
+                uuids
+            };
+
+            let filter = SearchFilter::default();
+            assert_eq!(
+                sorted(found(&pool, "topic", filter).await),
+                expect(&[&active, &pinned])
+            );
+            let filter = SearchFilter {
+                archived: true,
+                deleted: true,
+                ..Default::default()
+            };
+            assert_eq!(
+                sorted(found(&pool, "topic", filter).await),
+                expect(&[&active, &archived, &deleted, &pinned])
+            );
+            let filter = SearchFilter {
+                pinned: true,
+                ..Default::default()
+            };
+            assert_eq!(found(&pool, "topic", filter).await, [pinned.uuid.as_str()]);
+        });
+    }
+}
@@ -0,0 +1,408 @@
+//! The trash: drafts with `deleted_at` set.
+//!
+//! Drafts left in the trash longer than the retention period are purged by a
+//! background job that runs at startup and then daily. Purging a draft also
+//! removes its revisions and its Automerge document mapping and chunks, all
+//! in one transaction. The retention period is saved to `trash.json` in the
+//! profile's directory.
+
+use super::{ChangeKind, Draft, notify, now};
+use crate::{db, profiles};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Settings file name inside the app config directory
+const FILE_NAME: &str = "trash.json";
+
+/// How often expired drafts are purged while the app runs
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashSettings {
+    /// Days a draft stays in the trash before it's purged; 0 keeps it until emptied
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+/// What a purge removed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    /// UUIDs of the purged drafts
+    pub drafts: Vec<String>,
+    pub revisions: u64,
+    pub chunks: u64,
+    /// Size of the deleted content, revisions and Automerge chunks
+    pub deleted_bytes: u64,
+    /// How much smaller the database file got once the freed pages were vacuumed
+    pub reclaimed_bytes: u64,
+}
+
+/// Which drafts to purge
+#[derive(Debug, Clone, Copy)]
+pub enum Selection<'a> {
+    /// One draft, whether or not it's in the trash
+    Draft(&'a str),
+    /// Everything in the trash
+    All,
+    /// Drafts in the trash for more than this many days
+    OlderThan(u32),
+}
+
+/// Settings file of the current profile
+fn settings_path() -> Option<PathBuf> {
+    profiles::dir(&profiles::current())
+        .map(|dir| dir.join(FILE_NAME))
+        .ok()
+}
+
+/// Settings saved at `path`, or the defaults
+fn read_settings(path: Option<&PathBuf>) -> TrashSettings {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Trash settings, saved to [`FILE_NAME`]
+pub struct TrashState {
+    path: Mutex<Option<PathBuf>>,
+    settings: Mutex<TrashSettings>,
+}
+
+impl TrashState {
+    /// Load settings, with the default retention period if none are saved
+    pub fn load() -> Self {
+        let path = settings_path();
+        Self {
+            settings: Mutex::new(read_settings(path.as_ref())),
+            path: Mutex::new(path),
+        }
+    }
+
+    /// Switch to the current profile's settings, after a profile switch
+    pub fn reload(&self) {
+        let path = settings_path();
+        *self.settings.lock().unwrap() = read_settings(path.as_ref());
+        *self.path.lock().unwrap() = path;
+    }
+
+    pub fn settings(&self) -> TrashSettings {
+        *self.settings.lock().unwrap()
+    }
+
+    /// Change how long drafts stay in the trash
+    pub fn set_retention_days(&self, days: u32) -> Result<(), String> {
+        let settings = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.retention_days = days;
+            *settings
+        };
+
+        let Some(path) = self.path.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&settings)
+            .map_err(|e| format!("Failed to serialize trash settings: {}", e))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+}
+
+/// Drafts in the trash, most recently deleted first
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Draft>, String> {
+    sqlx::query_as::<_, Draft>(
+        "SELECT uuid, content, created_at, modified_at, deleted_at, archived, pinned
+         FROM draft WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list trash: {}", e))
+}
+
+/// Delete drafts for good, with everything stored for them
+pub async fn purge(pool: &SqlitePool, selection: Selection<'_>) -> Result<PurgeReport, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let uuids: Vec<String> = match selection {
+        Selection::Draft(uuid) => {
+            sqlx::query_scalar("SELECT uuid FROM draft WHERE uuid = ?")
+                .bind(uuid)
+                .fetch_all(&mut *tx)
+                .await
+        }
+        Selection::All => {
+            sqlx::query_scalar("SELECT uuid FROM draft WHERE deleted_at IS NOT NULL")
+                .fetch_all(&mut *tx)
+                .await
+        }
+        Selection::OlderThan(days) => {
+            sqlx::query_scalar(
+                "SELECT uuid FROM draft
+                 WHERE deleted_at IS NOT NULL AND julianday(?) - julianday(deleted_at) > ?",
+            )
+            .bind(now())
+            .bind(days)
+            .fetch_all(&mut *tx)
+            .await
+        }
+    }
+    .map_err(|e| format!("Failed to find drafts to purge: {}", e))?;
+
+    let mut report = PurgeReport::default();
+    for uuid in uuids {
+        purge_one(&mut tx, &uuid, &mut report).await?;
+        report.drafts.push(uuid);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    if !report.drafts.is_empty() {
+        // The drafts are gone either way, so a failed vacuum only costs space
+        report.reclaimed_bytes = vacuum(pool).await.unwrap_or_else(|e| {
+            tracing::warn!("{}", e);
+            0
+        });
+    }
+    Ok(report)
+}
+
+/// Rebuild the database file without its free pages, returning how much it shrank
+async fn vacuum(pool: &SqlitePool) -> Result<u64, String> {
+    let before = file_size(pool).await?;
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to vacuum database: {}", e))?;
+    Ok(before.saturating_sub(file_size(pool).await?))
+}
+
+/// Size of the database file, from its page count
+async fn file_size(pool: &SqlitePool) -> Result<u64, String> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+    )
+    .fetch_one(pool)
+    .await
+    .map(|size| size.max(0) as u64)
+    .map_err(|e| format!("Failed to measure database: {}", e))
+}
+
+async fn purge_one(
+    conn: &mut SqliteConnection,
+    uuid: &str,
+    report: &mut PurgeReport,
+) -> Result<(), String> {
+    let chunks: Vec<i64> = sqlx::query_scalar(
+        "DELETE FROM automerge_chunk WHERE doc_id IN (
+             SELECT doc_id FROM automerge_doc_map WHERE draft_uuid = ?
+         ) RETURNING length(bytes)",
+    )
+    .bind(uuid)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to delete Automerge chunks of {}: {}", uuid, e))?;
+
+    sqlx::query("DELETE FROM automerge_doc_map WHERE draft_uuid = ?")
+        .bind(uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to delete Automerge mapping of {}: {}", uuid, e))?;
+
+    let revisions: Vec<i64> = sqlx::query_scalar(
+        "DELETE FROM draft_revision WHERE draft_uuid = ?
+         RETURNING COALESCE(length(CAST(content AS BLOB)), length(CAST(delta AS BLOB)), 0)",
+    )
+    .bind(uuid)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to delete revisions of {}: {}", uuid, e))?;
+
+    let content: i64 = sqlx::query_scalar(
+        "DELETE FROM draft WHERE uuid = ? RETURNING length(CAST(content AS BLOB))",
+    )
+    .bind(uuid)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to delete draft {}: {}", uuid, e))?;
+
+    report.chunks += chunks.len() as u64;
+    report.revisions += revisions.len() as u64;
+    report.deleted_bytes += chunks
+        .iter()
+        .chain(&revisions)
+        .chain([&content])
+        .map(|&bytes| bytes.max(0) as u64)
+        .sum::<u64>();
+    Ok(())
+}
+
+/// Purge drafts past the retention period, telling windows about each one
+pub async fn purge_expired(app: &AppHandle) -> Result<PurgeReport, String> {
+    let days = app.state::<TrashState>().settings().retention_days;
+    if days == 0 {
+        return Ok(PurgeReport::default());
+    }
+
+    let pool = db::pool(app).await?;
+    let report = purge(&pool, Selection::OlderThan(days)).await?;
+    for uuid in &report.drafts {
+        notify(app, uuid, ChangeKind::Deleted);
+    }
+    Ok(report)
+}
+
+/// Purge expired drafts now and then every [`PURGE_INTERVAL`]
+pub fn schedule(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match purge_expired(&app).await {
+                Ok(report) if report.drafts.is_empty() => {}
+                Ok(report) => tracing::info!(
+                    "Purged {} draft(s) from the trash, deleting {} bytes and reclaiming {} bytes",
+                    report.drafts.len(),
+                    report.deleted_bytes,
+                    report.reclaimed_bytes
+                ),
+                Err(e) => tracing::warn!("{}", e),
+            }
+            tokio::time::sleep(PURGE_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drafts;
+    use crate::testing;
+
+    /// Give a draft revisions and Automerge data
+    async fn fill(pool: &SqlitePool, uuid: &str) {
+        drafts::save(pool, uuid, &"text\n".repeat(2000))
+            .await
+            .unwrap();
+        drafts::save(pool, uuid, "short\n").await.unwrap();
+        sqlx::query(
+            "INSERT INTO automerge_doc_map (draft_uuid, doc_id, created_at) VALUES (?1, ?1, ?2)",
+        )
+        .bind(uuid)
+        .bind(now())
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO automerge_chunk (doc_id, chunk_type, chunk_id, bytes, created_at)
+             VALUES (?1, 'snapshot', 'a', zeroblob(5000), ?2),
+                    (?1, 'incremental', 'b', zeroblob(5000), ?2)",
+        )
+        .bind(uuid)
+        .bind(now())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn count(pool: &SqlitePool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// Move a draft to the trash `days` ago
+    async fn trash(pool: &SqlitePool, uuid: &str, days: u32) {
+        drafts::delete(pool, uuid).await.unwrap();
+        sqlx::query("UPDATE draft SET deleted_at = datetime('now', ?) WHERE uuid = ?")
+            .bind(format!("-{} days", days))
+            .bind(uuid)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn purge_removes_everything_stored_for_a_draft() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let gone = drafts::create(&pool, "").await.unwrap();
+            let kept = drafts::create(&pool, "").await.unwrap();
+            fill(&pool, &gone.uuid).await;
+            fill(&pool, &kept.uuid).await;
+            trash(&pool, &gone.uuid, 0).await;
+
+            let report = purge(&pool, Selection::All).await.unwrap();
+            assert_eq!(report.drafts, [gone.uuid.as_str()]);
+            assert_eq!(report.revisions, 2);
+            assert_eq!(report.chunks, 2);
+            assert!(report.deleted_bytes > 10_000);
+            assert!(report.reclaimed_bytes > 0);
+
+            assert!(drafts::get(&pool, &gone.uuid).await.unwrap().is_none());
+            assert_eq!(count(&pool, "draft").await, 1);
+            assert_eq!(count(&pool, "draft_revision").await, 2);
+            assert_eq!(count(&pool, "automerge_doc_map").await, 1);
+            assert_eq!(count(&pool, "automerge_chunk").await, 2);
+        });
+    }
+
+    #[test]
+    fn purges_only_drafts_past_retention() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let old = drafts::create(&pool, "old").await.unwrap();
+            let recent = drafts::create(&pool, "recent").await.unwrap();
+            let active = drafts::create(&pool, "active").await.unwrap();
+            trash(&pool, &old.uuid, 40).await;
+            trash(&pool, &recent.uuid, 5).await;
+
+            let report = purge(&pool, Selection::OlderThan(30)).await.unwrap();
+            assert_eq!(report.drafts, [old.uuid.as_str()]);
+
+            let left: Vec<String> = list(&pool)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|d| d.uuid)
+                .collect();
+            assert_eq!(left, [recent.uuid.as_str()]);
+            assert!(drafts::get(&pool, &active.uuid).await.unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn restored_drafts_leave_the_trash() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "back").await.unwrap();
+            trash(&pool, &draft.uuid, 40).await;
+            assert_eq!(list(&pool).await.unwrap().len(), 1);
+
+            let restored = drafts::restore(&pool, &draft.uuid).await.unwrap();
+            assert!(restored.deleted_at.is_none());
+            assert!(list(&pool).await.unwrap().is_empty());
+
+            let report = purge(&pool, Selection::OlderThan(30)).await.unwrap();
+            assert!(report.drafts.is_empty());
+            assert_eq!(report.reclaimed_bytes, 0);
+            assert!(drafts::get(&pool, &draft.uuid).await.unwrap().is_some());
+        });
+    }
+}
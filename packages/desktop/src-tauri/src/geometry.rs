@@ -0,0 +1,435 @@
+//! Window geometry persistence and capture window placement.
+//!
+//! Size, position and maximized state are saved to `window-state.json` in
+//! the profile's directory, keyed by window label and by monitor layout, so
+//! docking and undocking a laptop each restore their own arrangement. When a
+//! layout has nothing saved, the window's last geometry from any layout is
+//! used, clamped onto a monitor that still exists.
+
+use crate::{profiles, windows};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalRect, PhysicalSize, Runtime,
+    WebviewWindow, Window, WindowEvent,
+};
+
+/// State file name inside the profile's directory
+const FILE_NAME: &str = "window-state.json";
+
+/// Where the capture window appears when shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapturePlacement {
+    /// Centered on the monitor under the cursor
+    #[default]
+    CenterOnCursorMonitor,
+    /// With its top-left corner at the cursor
+    AtCursor,
+    /// Wherever it was last left
+    Remembered,
+}
+
+/// Outer position and inner size in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Store {
+    capture_placement: CapturePlacement,
+    /// Geometry by monitor layout, then by window label
+    layouts: HashMap<String, HashMap<String, Geometry>>,
+    /// Most recent geometry by window label, for layouts seen for the first time
+    last: HashMap<String, Geometry>,
+}
+
+/// Saved window geometry and capture placement
+pub struct GeometryState {
+    path: Mutex<Option<PathBuf>>,
+    store: Mutex<Store>,
+}
+
+impl GeometryState {
+    /// Load saved state, starting fresh if it's missing or unreadable
+    pub fn load() -> Self {
+        let path = state_path();
+        Self {
+            store: Mutex::new(read_store(path.as_ref())),
+            path: Mutex::new(path),
+        }
+    }
+
+    /// Switch to the current profile's state, after a profile switch
+    pub fn reload(&self) {
+        let path = state_path();
+        *self.store.lock().unwrap() = read_store(path.as_ref());
+        *self.path.lock().unwrap() = path;
+    }
+
+    pub fn capture_placement(&self) -> CapturePlacement {
+        self.store.lock().unwrap().capture_placement
+    }
+
+    pub fn set_capture_placement(&self, placement: CapturePlacement) -> Result<(), String> {
+        self.store.lock().unwrap().capture_placement = placement;
+        self.save()
+    }
+
+    /// Restore a window's saved size, position and maximized state
+    pub fn restore<R: Runtime>(&self, window: &WebviewWindow<R>) {
+        let Some(geometry) = self.saved(window) else {
+            return;
+        };
+
+        let result = window
+            .set_size(PhysicalSize::new(geometry.width, geometry.height))
+            .and_then(|_| window.set_position(PhysicalPosition::new(geometry.x, geometry.y)))
+            .and_then(|_| {
+                if geometry.maximized {
+                    window.maximize()
+                } else {
+                    Ok(())
+                }
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to restore {} window geometry: {}", window.label(), e);
+        }
+    }
+
+    /// Size and move the capture window according to the placement policy
+    pub fn place_capture<R: Runtime>(&self, window: &WebviewWindow<R>) {
+        let saved = self.saved(window);
+        if let Some(geometry) = saved {
+            let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+        }
+
+        let position = match self.capture_placement() {
+            CapturePlacement::Remembered => saved.map(|g| PhysicalPosition::new(g.x, g.y)),
+            placement => cursor_position(window, placement),
+        };
+
+        if let Some(position) = position
+            && let Err(e) = window.set_position(position)
+        {
+            tracing::warn!("Failed to place capture window: {}", e);
+        }
+    }
+
+    /// Record geometry when a window loses focus or closes
+    pub fn on_window_event<R: Runtime>(&self, window: &Window<R>, event: &WindowEvent) {
+        if !matches!(window.label(), windows::MAIN | windows::CAPTURE) {
+            return;
+        }
+        if !matches!(
+            event,
+            WindowEvent::Focused(false) | WindowEvent::CloseRequested { .. }
+        ) {
+            return;
+        }
+
+        if let Err(e) = self.record(window) {
+            tracing::warn!("Failed to save {} window geometry: {}", window.label(), e);
+        }
+    }
+
+    /// Record the geometry of every open window, for the shutdown sequence
+    ///
+    /// Exiting from the tray or by signal closes windows without the events
+    /// that usually record them.
+    pub fn record_all<R: Runtime>(&self, app: &AppHandle<R>) {
+        for label in [windows::MAIN, windows::CAPTURE] {
+            let Some(window) = app.get_webview_window(label) else {
+                continue;
+            };
+            if let Err(e) = self.record(&window.as_ref().window()) {
+                tracing::warn!("Failed to save {} window geometry: {}", window.label(), e);
+            }
+        }
+    }
+
+    fn record<R: Runtime>(&self, window: &Window<R>) -> Result<(), String> {
+        if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+            return Ok(());
+        }
+
+        let maximized = window.is_maximized().map_err(|e| e.to_string())?;
+        let layout = layout_key(&window.available_monitors().map_err(|e| e.to_string())?);
+        let label = window.label().to_string();
+
+        {
+            let mut store = self.store.lock().unwrap();
+            // Keep the unmaximized geometry, so unmaximizing after a restore has somewhere to go
+            let geometry = match store.last.get(&label) {
+                Some(previous) if maximized => Geometry {
+                    maximized,
+                    ..*previous
+                },
+                _ => {
+                    let position = window.outer_position().map_err(|e| e.to_string())?;
+                    let size = window.inner_size().map_err(|e| e.to_string())?;
+                    Geometry {
+                        x: position.x,
+                        y: position.y,
+                        width: size.width,
+                        height: size.height,
+                        maximized,
+                    }
+                }
+            };
+
+            if store.layouts.get(&layout).and_then(|l| l.get(&label)) == Some(&geometry) {
+                return Ok(());
+            }
+            store
+                .layouts
+                .entry(layout)
+                .or_default()
+                .insert(label.clone(), geometry);
+            store.last.insert(label, geometry);
+        }
+
+        self.save()
+    }
+
+    /// Saved geometry for the current monitor layout, clamped onto a connected monitor
+    fn saved<R: Runtime>(&self, window: &WebviewWindow<R>) -> Option<Geometry> {
+        let monitors = window.available_monitors().ok()?;
+        let store = self.store.lock().unwrap();
+        let geometry = store
+            .layouts
+            .get(&layout_key(&monitors))
+            .and_then(|layout| layout.get(window.label()))
+            .or_else(|| store.last.get(window.label()))?;
+        Some(clamp(*geometry, &work_areas(&monitors)))
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = self.path.lock().unwrap().clone() else {
+            return Ok(());
+        };
+
+        let json = serde_json::to_string_pretty(&*self.store.lock().unwrap())
+            .map_err(|e| format!("Failed to serialize window state: {}", e))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+}
+
+/// State file of the current profile
+fn state_path() -> Option<PathBuf> {
+    profiles::dir(&profiles::current())
+        .map(|dir| dir.join(FILE_NAME))
+        .inspect_err(|e| tracing::warn!("Window geometry won't be saved: {}", e))
+        .ok()
+}
+
+/// State saved at `path`, or a fresh one
+fn read_store(path: Option<&PathBuf>) -> Store {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| {
+            serde_json::from_str(&json)
+                .inspect_err(|e| tracing::warn!("Ignoring invalid {}: {}", FILE_NAME, e))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Identify a monitor arrangement by each monitor's name, position and size
+fn layout_key(monitors: &[Monitor]) -> String {
+    let mut parts: Vec<String> = monitors
+        .iter()
+        .map(|m| {
+            format!(
+                "{}:{}x{}@{},{}",
+                m.name().map(String::as_str).unwrap_or("?"),
+                m.size().width,
+                m.size().height,
+                m.position().x,
+                m.position().y
+            )
+        })
+        .collect();
+    parts.sort();
+    parts.join(";")
+}
+
+/// Work area of each monitor, in the same order
+fn work_areas(monitors: &[Monitor]) -> Vec<PhysicalRect<i32, u32>> {
+    monitors.iter().map(|m| *m.work_area()).collect()
+}
+
+/// Keep a window on screen
+///
+/// A window whose center is in a work area is kept within that area;
+/// otherwise it's centered on the first one. Either way it's shrunk to fit.
+fn clamp(geometry: Geometry, areas: &[PhysicalRect<i32, u32>]) -> Geometry {
+    let center_x = geometry.x + (geometry.width / 2) as i32;
+    let center_y = geometry.y + (geometry.height / 2) as i32;
+    let containing = areas.iter().find(|area| {
+        center_x >= area.position.x
+            && center_x < area.position.x + area.size.width as i32
+            && center_y >= area.position.y
+            && center_y < area.position.y + area.size.height as i32
+    });
+
+    let Some(area) = containing.or_else(|| areas.first()) else {
+        return geometry;
+    };
+    let width = geometry.width.min(area.size.width);
+    let height = geometry.height.min(area.size.height);
+    let max_x = area.position.x + (area.size.width - width) as i32;
+    let max_y = area.position.y + (area.size.height - height) as i32;
+
+    let (x, y) = if containing.is_some() {
+        (
+            geometry.x.clamp(area.position.x, max_x),
+            geometry.y.clamp(area.position.y, max_y),
+        )
+    } else {
+        (
+            area.position.x + ((area.size.width - width) / 2) as i32,
+            area.position.y + ((area.size.height - height) / 2) as i32,
+        )
+    };
+
+    Geometry {
+        x,
+        y,
+        width,
+        height,
+        ..geometry
+    }
+}
+
+/// Capture window position for the cursor-based placements
+fn cursor_position<R: Runtime>(
+    window: &WebviewWindow<R>,
+    placement: CapturePlacement,
+) -> Option<PhysicalPosition<i32>> {
+    // Not available on every platform (e.g. Wayland); leave the window where it is
+    let cursor = window.cursor_position().ok()?;
+    let monitor = window
+        .monitor_from_point(cursor.x, cursor.y)
+        .ok()
+        .flatten()?;
+    let size = window.outer_size().ok()?;
+    let area = monitor.work_area();
+
+    let (x, y) = match placement {
+        CapturePlacement::AtCursor => (cursor.x as i32, cursor.y as i32),
+        _ => (
+            area.position.x + (area.size.width as i32 - size.width as i32) / 2,
+            area.position.y + (area.size.height as i32 - size.height as i32) / 2,
+        ),
+    };
+
+    let geometry = clamp(
+        Geometry {
+            x,
+            y,
+            width: size.width,
+            height: size.height,
+            maximized: false,
+        },
+        std::slice::from_ref(monitor.work_area()),
+    );
+    Some(PhysicalPosition::new(geometry.x, geometry.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+    fn area(x: i32, y: i32, width: u32, height: u32) -> PhysicalRect<i32, u32> {
+        PhysicalRect {
+            position: PhysicalPosition::new(x, y),
+            size: PhysicalSize::new(width, height),
+        }
+    }
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> Geometry {
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+        }
+    }
+
+    /// A laptop screen with a taller monitor to its right
+    fn areas() -> Vec<PhysicalRect<i32, u32>> {
+        vec![area(0, 0, 1920, 1080), area(1920, 0, 2560, 1440)]
+    }
+
+    #[test]
+    fn leaves_windows_on_a_monitor_alone() {
+        let window = geometry(2200, 100, 800, 600);
+        assert_eq!(clamp(window, &areas()), window);
+    }
+
+    #[test]
+    fn pulls_partly_off_screen_windows_back() {
+        // Centered on the second monitor, but over its right and bottom edges
+        let window = geometry(3800, 1000, 800, 600);
+        assert_eq!(clamp(window, &areas()), geometry(3680, 840, 800, 600));
+    }
+
+    #[test]
+    fn centers_windows_from_a_missing_monitor() {
+        let window = geometry(5000, 200, 800, 600);
+        assert_eq!(clamp(window, &areas()), geometry(560, 240, 800, 600));
+
+        let maximized = Geometry {
+            maximized: true,
+            ..window
+        };
+        assert!(clamp(maximized, &areas()).maximized);
+        assert_eq!(clamp(window, &[]), window);
+    }
+
+    #[test]
+    fn shrinks_windows_larger_than_the_work_area() {
+        let window = geometry(-100, -50, 3000, 2000);
+        assert_eq!(clamp(window, &areas()), geometry(0, 0, 1920, 1080));
+
+        // A panel offsets the work area
+        let panel = [area(0, 32, 1920, 1048)];
+        assert_eq!(clamp(window, &panel), geometry(0, 32, 1920, 1048));
+    }
+
+    #[test]
+    fn records_open_windows_on_shutdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        let state = GeometryState {
+            path: Mutex::new(Some(path.clone())),
+            store: Mutex::new(Store::default()),
+        };
+
+        let app = tauri::test::mock_app();
+        let main = WebviewWindowBuilder::new(&app, windows::MAIN, WebviewUrl::default())
+            .build()
+            .unwrap();
+        main.show().unwrap();
+        state.record_all(app.handle());
+
+        let saved: Store = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(saved.last.contains_key(windows::MAIN));
+        assert!(!saved.last.contains_key(windows::CAPTURE));
+    }
+}
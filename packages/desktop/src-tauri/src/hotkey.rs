@@ -1,13 +1,88 @@
 mod global;
 #[cfg(target_os = "linux")]
 mod evdev;
+#[cfg(target_os = "linux")]
+mod expander;
 mod shortcut;
 
-use std::sync::Arc;
+#[cfg(target_os = "linux")]
+pub use expander::TextExpander;
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::Manager;
+
+/// Backend of the most recently registered hotkey, for crash reports
+static ACTIVE_BACKEND: Mutex<&str> = Mutex::new("none");
+
+/// Name of the backend the last hotkey was registered with
+pub fn active_backend() -> &'static str {
+    ACTIVE_BACKEND.try_lock().map(|b| *b).unwrap_or("unknown")
+}
+
+/// Hotkey backend status, for diagnostics
+#[derive(Debug, Serialize)]
+pub struct BackendStatus {
+    pub backend: &'static str,
+    /// Whether the user is in the `input` group evdev needs
+    pub input_group: Option<bool>,
+    /// Keyboards evdev can read, or why none were found
+    pub keyboards: Result<Vec<String>, String>,
+}
+
+/// Current backend and the devices evdev would use
+pub fn backend_status() -> BackendStatus {
+    #[cfg(target_os = "linux")]
+    let (input_group, keyboards) = (
+        evdev::check_input_group().ok(),
+        evdev::describe_keyboard_devices(),
+    );
+    #[cfg(not(target_os = "linux"))]
+    let (input_group, keyboards) = (None, Err("evdev is only available on Linux".to_string()));
+
+    BackendStatus {
+        backend: active_backend(),
+        input_group,
+        keyboards,
+    }
+}
+
+/// What a registered hotkey does when it fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HotkeyAction {
+    /// Open the quick-capture window
+    Capture,
+    /// Append the current selection to the inbox draft without showing a window
+    SilentCapture,
+}
+
+impl HotkeyAction {
+    /// Perform the action, unless hotkeys are paused
+    ///
+    /// `pressed` is when the key event happened, for the capture latency.
+    pub fn trigger(self, app: &tauri::AppHandle, pressed: Instant) {
+        // Covers key event to window shown, for `--trace`; the capture window
+        // keeps it open until it has focus
+        let _span = tracing::debug_span!("hotkey", action = ?self).entered();
+        let paused = app
+            .try_state::<crate::conf::SettingsState>()
+            .is_some_and(|state| state.hotkeys_paused());
+        if paused {
+            tracing::debug!("Ignoring {:?} hotkey while paused", self);
+            return;
+        }
+
+        match self {
+            HotkeyAction::Capture => crate::windows::show_capture_since(app, pressed),
+            HotkeyAction::SilentCapture => crate::capture::silent_capture(app.clone()),
+        }
+    }
+}
 
 /// Trait for cross-platform hotkey management
 pub trait HotkeyManager: Send + Sync {
-    /// Register the quick-capture hotkey
+    /// Register the hotkey
     fn register(&self) -> Result<(), String>;
 
     /// Unregister the hotkey
@@ -18,13 +93,15 @@ pub trait HotkeyManager: Send + Sync {
 pub fn create_manager(
     app: tauri::AppHandle,
     shortcut: &str,
+    action: HotkeyAction,
 ) -> Result<Arc<dyn HotkeyManager>, String> {
     #[cfg(target_os = "linux")]
     {
         // Try evdev first on Linux
-        match evdev::EvdevHotkeyManager::new(app.clone(), shortcut) {
+        match evdev::EvdevHotkeyManager::new(app.clone(), shortcut, action) {
             Ok(manager) => {
                 tracing::info!("Using evdev backend for hotkeys");
+                *ACTIVE_BACKEND.lock().unwrap() = "evdev";
                 return Ok(Arc::new(manager));
             }
             Err(e) => {
@@ -39,6 +116,7 @@ pub fn create_manager(
 
     // Use Global Shortcut for macOS, Windows, and Linux fallback
     tracing::info!("Using Global Shortcut backend for hotkeys");
-    let manager = global::GlobalShortcutManager::new(app, shortcut)?;
+    let manager = global::GlobalShortcutManager::new(app, shortcut, action)?;
+    *ACTIVE_BACKEND.lock().unwrap() = "global-shortcut";
     Ok(Arc::new(manager))
 }
@@ -1,4 +1,4 @@
-use super::{shortcut::ShortcutSpec, HotkeyManager};
+use super::{shortcut::ShortcutSpec, HotkeyAction, HotkeyManager};
 use evdev::{Device, InputEventKind, Key};
 use std::collections::HashSet;
 use std::os::unix::io::AsRawFd;
@@ -6,19 +6,24 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::Emitter;
+use std::time::{Instant, SystemTime};
 
 pub struct EvdevHotkeyManager {
     app: tauri::AppHandle,
     target_key: Key,
     required_modifiers: HashSet<Key>,
+    action: HotkeyAction,
     registered: AtomicBool,
     stop_flag: Arc<AtomicBool>,
     listener: Arc<Mutex<Option<std::thread::JoinHandle<()>>>>,
 }
 
 impl EvdevHotkeyManager {
-    pub fn new(app: tauri::AppHandle, shortcut_str: &str) -> Result<Self, String> {
+    pub fn new(
+        app: tauri::AppHandle,
+        shortcut_str: &str,
+        action: HotkeyAction,
+    ) -> Result<Self, String> {
         // Check for input group permissions
         match check_input_group() {
             Ok(true) => {
@@ -43,6 +48,7 @@ impl EvdevHotkeyManager {
             app,
             target_key,
             required_modifiers,
+            action,
             registered: AtomicBool::new(false),
             stop_flag: Arc::new(AtomicBool::new(false)),
             listener: Arc::new(Mutex::new(None)),
@@ -70,10 +76,11 @@ impl HotkeyManager for EvdevHotkeyManager {
         let app = self.app.clone();
         let target_key = self.target_key;
         let required_modifiers = self.required_modifiers.clone();
+        let action = self.action;
 
         // Spawn the listener thread
         let listener_handle = std::thread::spawn(move || {
-            evdev_listener_loop(devices, app, stop_flag, target_key, required_modifiers);
+            evdev_listener_loop(devices, app, stop_flag, target_key, required_modifiers, action);
         });
 
         // Store the listener handle
@@ -108,7 +115,7 @@ impl HotkeyManager for EvdevHotkeyManager {
 }
 
 /// Find all keyboard input devices by probing /dev/input/event* devices
-fn find_keyboard_devices() -> Result<Vec<PathBuf>, String> {
+pub(super) fn find_keyboard_devices() -> Result<Vec<PathBuf>, String> {
     let mut keyboards = Vec::new();
 
     let input_dir = std::fs::read_dir("/dev/input")
@@ -168,16 +175,23 @@ fn find_keyboard_devices() -> Result<Vec<PathBuf>, String> {
     Ok(keyboards)
 }
 
-/// Main listener loop running in a blocking thread
-fn evdev_listener_loop(
-    device_paths: Vec<PathBuf>,
-    app: tauri::AppHandle,
-    stop_flag: Arc<AtomicBool>,
-    target_key: Key,
-    modifier_keys: HashSet<Key>,
-) {
-    // Open all keyboard devices in non-blocking mode
-    let mut devices: Vec<Device> = device_paths
+/// Keyboards with their device names, for diagnostics
+pub(super) fn describe_keyboard_devices() -> Result<Vec<String>, String> {
+    Ok(find_keyboard_devices()?
+        .into_iter()
+        .map(|path| {
+            let name = Device::open(&path)
+                .ok()
+                .and_then(|device| device.name().map(str::to_string))
+                .unwrap_or_else(|| "unknown".to_string());
+            format!("{} ({})", path.display(), name)
+        })
+        .collect())
+}
+
+/// Open devices in non-blocking mode so `fetch_events` returns immediately
+pub(super) fn open_nonblocking(device_paths: &[PathBuf]) -> Vec<Device> {
+    device_paths
         .iter()
         .filter_map(|path| match Device::open(path) {
             Ok(device) => {
@@ -197,7 +211,32 @@ fn evdev_listener_loop(
                 None
             }
         })
-        .collect();
+        .collect()
+}
+
+/// When a kernel-timestamped input event happened, on the monotonic clock
+///
+/// The listener only polls every few milliseconds, so the time it reads an
+/// event understates the hotkey latency.
+fn event_instant(timestamp: SystemTime) -> Instant {
+    let now = Instant::now();
+    let age = SystemTime::now()
+        .duration_since(timestamp)
+        .unwrap_or_default();
+    now.checked_sub(age).unwrap_or(now)
+}
+
+/// Main listener loop running in a blocking thread
+fn evdev_listener_loop(
+    device_paths: Vec<PathBuf>,
+    app: tauri::AppHandle,
+    stop_flag: Arc<AtomicBool>,
+    target_key: Key,
+    modifier_keys: HashSet<Key>,
+    action: HotkeyAction,
+) {
+    // Open all keyboard devices in non-blocking mode
+    let mut devices = open_nonblocking(&device_paths);
 
     if devices.is_empty() {
         tracing::error!("No keyboard devices could be opened");
@@ -211,7 +250,8 @@ fn evdev_listener_loop(
     let mut is_pressed = false;
 
     tracing::info!(
-        "Listening for Ctrl+Shift+C (target: {:?}, modifiers: {:?})",
+        "Listening for {:?} hotkey (target: {:?}, modifiers: {:?})",
+        action,
         target_key,
         modifier_keys
     );
@@ -285,13 +325,13 @@ fn evdev_listener_loop(
                                     1 if !is_pressed => {
                                         // Key press (not repeat)
                                         is_pressed = true;
-                                        tracing::info!("Quick capture hotkey triggered (evdev)");
-                                        let _ = app.emit("hotkey:capture", ());
+                                        tracing::info!("{:?} hotkey triggered (evdev)", action);
+                                        action.trigger(&app, event_instant(event.timestamp()));
                                     }
                                     0 if is_pressed => {
                                         // Key release
                                         is_pressed = false;
-                                        tracing::debug!("Quick capture hotkey released (evdev)");
+                                        tracing::debug!("{:?} hotkey released (evdev)", action);
                                     }
                                     2 => {
                                         // Key repeat - ignore
@@ -314,7 +354,7 @@ fn evdev_listener_loop(
 }
 
 /// Check if the current user is in the 'input' group
-fn check_input_group() -> Result<bool, String> {
+pub(super) fn check_input_group() -> Result<bool, String> {
     let output = Command::new("groups")
         .output()
         .map_err(|e| format!("Failed to execute 'groups' command: {}", e))?;
@@ -341,7 +381,7 @@ fn get_username() -> Result<String, String> {
 }
 
 /// Get a helpful error message for missing input group permissions
-fn get_permission_error_message() -> String {
+pub(super) fn get_permission_error_message() -> String {
     let username = get_username().unwrap_or_else(|_| "your_username".to_string());
     
     format!(
@@ -351,3 +391,24 @@ fn get_permission_error_message() -> String {
         username
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn dates_events_by_their_timestamp() {
+        let before = Instant::now();
+        let pressed = event_instant(SystemTime::now() - Duration::from_millis(50));
+        assert!(before.duration_since(pressed) >= Duration::from_millis(45));
+        assert!(before.duration_since(pressed) < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn clamps_future_timestamps_to_now() {
+        let before = Instant::now();
+        let pressed = event_instant(SystemTime::now() + Duration::from_secs(60));
+        assert!(pressed >= before);
+    }
+}
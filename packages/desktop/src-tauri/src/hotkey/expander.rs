@@ -0,0 +1,537 @@
+//! Opt-in global text expansion built on the evdev keyboard listener.
+//!
+//! Any active draft whose first line is a trigger such as `;sig` is a snippet;
+//! the rest of the draft is its expansion. When the trigger is typed anywhere,
+//! it is erased with backspaces and the expansion is typed through a uinput
+//! virtual keyboard.
+//!
+//! Privacy: typed characters only ever live in a small in-memory buffer that is
+//! cleared on every non-printable key, while paused, and on stop. Keystrokes
+//! are never logged, emitted as events, or written to disk.
+
+use super::evdev::{
+    check_input_group, find_keyboard_devices, get_permission_error_message, open_nonblocking,
+};
+use crate::{db, drafts};
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{AttributeSet, Device, EventType, InputEvent, InputEventKind, Key};
+use std::collections::{HashSet, VecDeque};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tauri::{EventId, Listener};
+
+/// Snippet drafts start with this character
+const TRIGGER_PREFIX: char = ';';
+
+/// Longest trigger that can be matched; also the size of the typed buffer
+const MAX_TRIGGER_LEN: usize = 32;
+
+/// Name of the uinput device used to type expansions
+const VIRTUAL_DEVICE_NAME: &str = "dashtext text expander";
+
+/// Pause between synthesized key taps so slow clients don't drop events
+const TAP_DELAY: Duration = Duration::from_millis(2);
+
+/// US layout: key, unshifted character, shifted character
+const KEYMAP: &[(Key, char, char)] = &[
+    (Key::KEY_A, 'a', 'A'),
+    (Key::KEY_B, 'b', 'B'),
+    (Key::KEY_C, 'c', 'C'),
+    (Key::KEY_D, 'd', 'D'),
+    (Key::KEY_E, 'e', 'E'),
+    (Key::KEY_F, 'f', 'F'),
+    (Key::KEY_G, 'g', 'G'),
+    (Key::KEY_H, 'h', 'H'),
+    (Key::KEY_I, 'i', 'I'),
+    (Key::KEY_J, 'j', 'J'),
+    (Key::KEY_K, 'k', 'K'),
+    (Key::KEY_L, 'l', 'L'),
+    (Key::KEY_M, 'm', 'M'),
+    (Key::KEY_N, 'n', 'N'),
+    (Key::KEY_O, 'o', 'O'),
+    (Key::KEY_P, 'p', 'P'),
+    (Key::KEY_Q, 'q', 'Q'),
+    (Key::KEY_R, 'r', 'R'),
+    (Key::KEY_S, 's', 'S'),
+    (Key::KEY_T, 't', 'T'),
+    (Key::KEY_U, 'u', 'U'),
+    (Key::KEY_V, 'v', 'V'),
+    (Key::KEY_W, 'w', 'W'),
+    (Key::KEY_X, 'x', 'X'),
+    (Key::KEY_Y, 'y', 'Y'),
+    (Key::KEY_Z, 'z', 'Z'),
+    (Key::KEY_1, '1', '!'),
+    (Key::KEY_2, '2', '@'),
+    (Key::KEY_3, '3', '#'),
+    (Key::KEY_4, '4', '$'),
+    (Key::KEY_5, '5', '%'),
+    (Key::KEY_6, '6', '^'),
+    (Key::KEY_7, '7', '&'),
+    (Key::KEY_8, '8', '*'),
+    (Key::KEY_9, '9', '('),
+    (Key::KEY_0, '0', ')'),
+    (Key::KEY_MINUS, '-', '_'),
+    (Key::KEY_EQUAL, '=', '+'),
+    (Key::KEY_LEFTBRACE, '[', '{'),
+    (Key::KEY_RIGHTBRACE, ']', '}'),
+    (Key::KEY_SEMICOLON, ';', ':'),
+    (Key::KEY_APOSTROPHE, '\'', '"'),
+    (Key::KEY_GRAVE, '`', '~'),
+    (Key::KEY_BACKSLASH, '\\', '|'),
+    (Key::KEY_COMMA, ',', '<'),
+    (Key::KEY_DOT, '.', '>'),
+    (Key::KEY_SLASH, '/', '?'),
+    (Key::KEY_SPACE, ' ', ' '),
+    (Key::KEY_ENTER, '\n', '\n'),
+    (Key::KEY_TAB, '\t', '\t'),
+];
+
+/// A trigger and the text it expands to
+#[derive(Debug, Clone)]
+struct Snippet {
+    trigger: String,
+    expansion: String,
+}
+
+impl Snippet {
+    /// Parse a snippet from a draft: the first line is the trigger, the rest the expansion
+    fn from_content(content: &str) -> Option<Self> {
+        let (first_line, expansion) = content.split_once('\n').unwrap_or((content, ""));
+        let trigger = first_line.trim();
+
+        let is_trigger = trigger.len() > 1
+            && trigger.len() <= MAX_TRIGGER_LEN
+            && trigger.starts_with(TRIGGER_PREFIX)
+            && trigger.chars().all(|c| c.is_ascii_graphic());
+
+        if !is_trigger || expansion.trim().is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            trigger: trigger.to_string(),
+            expansion: expansion.trim_end().to_string(),
+        })
+    }
+}
+
+/// State shared between the expander and its listener thread
+#[derive(Clone, Default)]
+struct Shared {
+    snippets: Arc<RwLock<Vec<Snippet>>>,
+    exclusions: Arc<RwLock<Vec<String>>>,
+    paused: Arc<AtomicBool>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+pub struct TextExpander {
+    app: tauri::AppHandle,
+    shared: Shared,
+    running: AtomicBool,
+    listener: Mutex<Option<std::thread::JoinHandle<()>>>,
+    drafts_listener: Mutex<Option<EventId>>,
+}
+
+impl TextExpander {
+    pub fn new(app: tauri::AppHandle) -> Result<Self, String> {
+        if !check_input_group()? {
+            return Err(get_permission_error_message());
+        }
+
+        Ok(Self {
+            app,
+            shared: Shared::default(),
+            running: AtomicBool::new(false),
+            listener: Mutex::new(None),
+            drafts_listener: Mutex::new(None),
+        })
+    }
+
+    /// Start listening for triggers, skipping apps whose window class is in `exclusions`
+    pub async fn start(&self, exclusions: Vec<String>) -> Result<(), String> {
+        *self.shared.exclusions.write().unwrap() = exclusions;
+        self.reload_snippets().await?;
+
+        if self.running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Enumerate physical keyboards before creating our own virtual one
+        let devices = find_keyboard_devices()?;
+        let keyboard = create_virtual_keyboard()?;
+
+        self.shared.stop_flag.store(false, Ordering::SeqCst);
+        let shared = self.shared.clone();
+        let handle = std::thread::spawn(move || {
+            expander_loop(devices, keyboard, shared);
+        });
+        *self.listener.lock().unwrap() = Some(handle);
+
+        // Keep snippets current when drafts change
+        let app = self.app.clone();
+        let snippets = self.shared.snippets.clone();
+        let id = self.app.listen(drafts::CHANGED_EVENT, move |_| {
+            let app = app.clone();
+            let snippets = snippets.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = load_snippets(&app, &snippets).await {
+                    tracing::warn!("Failed to reload snippets: {}", e);
+                }
+            });
+        });
+        *self.drafts_listener.lock().unwrap() = Some(id);
+
+        self.running.store(true, Ordering::SeqCst);
+        tracing::info!("Text expansion enabled");
+        Ok(())
+    }
+
+    /// Stop listening and forget everything that was typed
+    pub fn stop(&self) {
+        if !self.running.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(id) = self.drafts_listener.lock().unwrap().take() {
+            self.app.unlisten(id);
+        }
+
+        self.shared.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.listener.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+
+        self.running.store(false, Ordering::SeqCst);
+        tracing::info!("Text expansion disabled");
+    }
+
+    /// Temporarily ignore all typing without tearing down the listener
+    pub fn set_paused(&self, paused: bool) {
+        self.shared.paused.store(paused, Ordering::SeqCst);
+        tracing::info!(
+            "Text expansion {}",
+            if paused { "paused" } else { "resumed" }
+        );
+    }
+
+    /// Reload snippets from the database
+    pub async fn reload_snippets(&self) -> Result<(), String> {
+        load_snippets(&self.app, &self.shared.snippets).await
+    }
+}
+
+async fn load_snippets(
+    app: &tauri::AppHandle,
+    snippets: &RwLock<Vec<Snippet>>,
+) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let prefix = TRIGGER_PREFIX.to_string();
+    let loaded: Vec<Snippet> = drafts::list_starting_with(&pool, &prefix)
+        .await?
+        .iter()
+        .filter_map(|draft| Snippet::from_content(&draft.content))
+        .collect();
+
+    tracing::debug!("Loaded {} text expansion snippet(s)", loaded.len());
+    *snippets.write().unwrap() = loaded;
+    Ok(())
+}
+
+/// Create the uinput keyboard used to type expansions
+fn create_virtual_keyboard() -> Result<VirtualDevice, String> {
+    let mut keys = AttributeSet::<Key>::new();
+    for (key, _, _) in KEYMAP {
+        keys.insert(*key);
+    }
+    keys.insert(Key::KEY_LEFTSHIFT);
+    keys.insert(Key::KEY_BACKSPACE);
+
+    VirtualDeviceBuilder::new()
+        .and_then(|builder| builder.name(VIRTUAL_DEVICE_NAME).with_keys(&keys))
+        .and_then(|builder| builder.build())
+        .map_err(|e| {
+            format!(
+                "Failed to create virtual keyboard: {}. \
+                 Text expansion needs write access to /dev/uinput.",
+                e
+            )
+        })
+}
+
+/// Listener loop running in a blocking thread
+fn expander_loop(device_paths: Vec<std::path::PathBuf>, mut keyboard: VirtualDevice, shared: Shared) {
+    let mut devices: Vec<Device> = open_nonblocking(&device_paths)
+        .into_iter()
+        .filter(|device| device.name() != Some(VIRTUAL_DEVICE_NAME))
+        .collect();
+
+    if devices.is_empty() {
+        tracing::error!("No keyboard devices could be opened for text expansion");
+        return;
+    }
+
+    let mut typed: VecDeque<char> = VecDeque::with_capacity(MAX_TRIGGER_LEN);
+    let mut shift_held = false;
+    let mut other_modifiers: HashSet<Key> = HashSet::new();
+
+    loop {
+        if shared.stop_flag.load(Ordering::SeqCst) {
+            typed.clear();
+            tracing::debug!("Text expansion listener stopping");
+            return;
+        }
+
+        for device in &mut devices {
+            let Ok(events) = device.fetch_events() else {
+                continue;
+            };
+
+            let keys: Vec<(Key, i32)> = events
+                .filter_map(|event| match event.kind() {
+                    InputEventKind::Key(key) => Some((key, event.value())),
+                    _ => None,
+                })
+                .collect();
+
+            for (key, value) in keys {
+                match key {
+                    Key::KEY_LEFTSHIFT | Key::KEY_RIGHTSHIFT => {
+                        shift_held = value != 0;
+                        continue;
+                    }
+                    Key::KEY_LEFTCTRL
+                    | Key::KEY_RIGHTCTRL
+                    | Key::KEY_LEFTALT
+                    | Key::KEY_RIGHTALT
+                    | Key::KEY_LEFTMETA
+                    | Key::KEY_RIGHTMETA => {
+                        if value == 0 {
+                            other_modifiers.remove(&key);
+                        } else {
+                            other_modifiers.insert(key);
+                        }
+                        typed.clear();
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                // Only presses and repeats type characters
+                if value == 0 {
+                    continue;
+                }
+
+                if shared.paused.load(Ordering::SeqCst) || !other_modifiers.is_empty() {
+                    typed.clear();
+                    continue;
+                }
+
+                if key == Key::KEY_BACKSPACE {
+                    typed.pop_back();
+                    continue;
+                }
+
+                match char_for_key(key, shift_held) {
+                    Some(c) if !c.is_control() => {
+                        if typed.len() == MAX_TRIGGER_LEN {
+                            typed.pop_front();
+                        }
+                        typed.push_back(c);
+                    }
+                    _ => {
+                        typed.clear();
+                        continue;
+                    }
+                }
+
+                let Some(snippet) = find_match(&typed, &shared.snippets.read().unwrap()) else {
+                    continue;
+                };
+                typed.clear();
+
+                if is_excluded(&shared.exclusions.read().unwrap()) {
+                    continue;
+                }
+
+                let erase = snippet.trigger.chars().count();
+                match type_expansion(&mut keyboard, erase, &snippet.expansion) {
+                    Ok(()) => tracing::debug!("Expanded a snippet"),
+                    Err(e) => tracing::warn!("Failed to type snippet expansion: {}", e),
+                }
+            }
+        }
+
+        // Small sleep to avoid busy-waiting
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Find a snippet whose trigger was just typed
+fn find_match(typed: &VecDeque<char>, snippets: &[Snippet]) -> Option<Snippet> {
+    let typed: String = typed.iter().collect();
+    snippets
+        .iter()
+        .find(|snippet| typed.ends_with(&snippet.trigger))
+        .cloned()
+}
+
+/// Character produced by a key on a US layout
+fn char_for_key(key: Key, shift: bool) -> Option<char> {
+    KEYMAP
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, plain, shifted)| if shift { *shifted } else { *plain })
+}
+
+/// Key (and whether shift is needed) that produces a character on a US layout
+fn key_for_char(c: char) -> Option<(Key, bool)> {
+    KEYMAP.iter().find_map(|(key, plain, shifted)| {
+        if c == *plain {
+            Some((*key, false))
+        } else if c == *shifted {
+            Some((*key, true))
+        } else {
+            None
+        }
+    })
+}
+
+/// Erase the trigger and type the expansion
+///
+/// Characters with no key on a US layout are skipped.
+fn type_expansion(keyboard: &mut VirtualDevice, erase: usize, text: &str) -> std::io::Result<()> {
+    for _ in 0..erase {
+        tap(keyboard, Key::KEY_BACKSPACE, false)?;
+    }
+
+    for c in text.chars() {
+        if let Some((key, shift)) = key_for_char(c) {
+            tap(keyboard, key, shift)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Press and release a key, optionally with shift held
+fn tap(keyboard: &mut VirtualDevice, key: Key, shift: bool) -> std::io::Result<()> {
+    let event = |key: Key, value: i32| InputEvent::new(EventType::KEY, key.code(), value);
+
+    if shift {
+        keyboard.emit(&[event(Key::KEY_LEFTSHIFT, 1)])?;
+    }
+    keyboard.emit(&[event(key, 1)])?;
+    keyboard.emit(&[event(key, 0)])?;
+    if shift {
+        keyboard.emit(&[event(Key::KEY_LEFTSHIFT, 0)])?;
+    }
+
+    std::thread::sleep(TAP_DELAY);
+    Ok(())
+}
+
+/// Whether the focused application is excluded from expansion
+///
+/// The window class is read with `xdotool`; when it can't be determined
+/// (e.g. on Wayland without XWayland focus), expansion is allowed.
+fn is_excluded(exclusions: &[String]) -> bool {
+    if exclusions.is_empty() {
+        return false;
+    }
+
+    let Some(class) = active_window_class() else {
+        return false;
+    };
+
+    exclusions.iter().any(|e| e.eq_ignore_ascii_case(&class))
+}
+
+/// Window class of the focused window
+fn active_window_class() -> Option<String> {
+    let output = Command::new("xdotool")
+        .args(["getactivewindow", "getwindowclassname"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let class = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!class.is_empty()).then_some(class)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(trigger: &str, expansion: &str) -> Snippet {
+        Snippet {
+            trigger: trigger.to_string(),
+            expansion: expansion.to_string(),
+        }
+    }
+
+    fn typed(text: &str) -> VecDeque<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn parses_trigger_and_expansion() {
+        let parsed = Snippet::from_content("  ;sig \nBest,\nJo\n\n").unwrap();
+        assert_eq!(parsed.trigger, ";sig");
+        assert_eq!(parsed.expansion, "Best,\nJo");
+    }
+
+    #[test]
+    fn rejects_drafts_that_are_not_snippets() {
+        for content in [
+            ";sig",
+            ";sig\n   \n",
+            ";\nbare prefix",
+            "sig\nno prefix",
+            ";two words\nspaces",
+            ";sïg\nnot ascii",
+        ] {
+            assert!(Snippet::from_content(content).is_none(), "{:?}", content);
+        }
+
+        let longest = format!(";{}", "a".repeat(MAX_TRIGGER_LEN - 1));
+        assert!(Snippet::from_content(&format!("{}\nx", longest)).is_some());
+        let too_long = format!(";{}", "a".repeat(MAX_TRIGGER_LEN));
+        assert!(Snippet::from_content(&format!("{}\nx", too_long)).is_none());
+    }
+
+    #[test]
+    fn matches_trigger_at_end_of_typed_text() {
+        let snippets = [snippet(";sig", "Best"), snippet(";addr", "Street")];
+        let found = find_match(&typed("hello ;addr"), &snippets).unwrap();
+        assert_eq!(found.expansion, "Street");
+        assert!(find_match(&typed(";sig "), &snippets).is_none());
+        assert!(find_match(&typed(";si"), &snippets).is_none());
+        assert!(find_match(&typed(""), &snippets).is_none());
+    }
+
+    #[test]
+    fn keymap_round_trips_every_character() {
+        for (key, plain, shifted) in KEYMAP {
+            assert_eq!(char_for_key(*key, false), Some(*plain));
+            assert_eq!(char_for_key(*key, true), Some(*shifted));
+            assert_eq!(key_for_char(*plain), Some((*key, false)));
+            if shifted != plain {
+                assert_eq!(key_for_char(*shifted), Some((*key, true)));
+            }
+        }
+    }
+
+    #[test]
+    fn keymap_types_snippet_text() {
+        assert_eq!(key_for_char(';'), Some((Key::KEY_SEMICOLON, false)));
+        assert_eq!(key_for_char('A'), Some((Key::KEY_A, true)));
+        assert_eq!(key_for_char('\n'), Some((Key::KEY_ENTER, false)));
+        assert_eq!(key_for_char('é'), None);
+        assert_eq!(char_for_key(Key::KEY_LEFTSHIFT, false), None);
+    }
+}
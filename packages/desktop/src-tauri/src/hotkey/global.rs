@@ -1,21 +1,27 @@
-use super::{HotkeyManager, shortcut::ShortcutSpec};
+use super::{HotkeyAction, HotkeyManager, shortcut::ShortcutSpec};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::Emitter;
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use std::time::Instant;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 pub struct GlobalShortcutManager {
     app: tauri::AppHandle,
     shortcut: Shortcut,
+    action: HotkeyAction,
     registered: AtomicBool,
 }
 
 impl GlobalShortcutManager {
-    pub fn new(app: tauri::AppHandle, shortcut_str: &str) -> Result<Self, String> {
+    pub fn new(
+        app: tauri::AppHandle,
+        shortcut_str: &str,
+        action: HotkeyAction,
+    ) -> Result<Self, String> {
         let spec: ShortcutSpec = shortcut_str.parse()?;
         let shortcut = spec.to_tauri()?;
         Ok(Self {
             app,
             shortcut,
+            action,
             registered: AtomicBool::new(false),
         })
     }
@@ -28,13 +34,18 @@ impl HotkeyManager for GlobalShortcutManager {
         }
 
         let app = self.app.clone();
+        let action = self.action;
 
         self.app
             .global_shortcut()
-            .on_shortcut(self.shortcut, move |_app, _shortcut, _event| {
-                tracing::info!("Quick capture hotkey triggered");
-                // Emit event that frontend will listen to
-                let _ = app.emit("hotkey:capture", ());
+            .on_shortcut(self.shortcut, move |_app, _shortcut, event| {
+                let pressed = Instant::now();
+                // The handler also runs on release
+                if event.state != ShortcutState::Pressed {
+                    return;
+                }
+                tracing::info!("{:?} hotkey triggered", action);
+                action.trigger(&app, pressed);
             })
             .map_err(|e| format!("Failed to register hotkey: {}", e))?;
 
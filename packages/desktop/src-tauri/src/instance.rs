@@ -0,0 +1,320 @@
+//! Single-instance enforcement.
+//!
+//! The first instance owns a Unix socket in `$XDG_RUNTIME_DIR`. Later launches
+//! connect to it, forward their command-line arguments as one JSON line, and
+//! exit. The running instance turns those arguments into a [`Request`] and
+//! focuses the right window.
+
+use crate::cli::{Cli, Command, OpenTarget};
+use crate::deeplink::DeepLink;
+use crate::{paths, profiles, windows};
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How long a later launch gets to send its arguments before it's dropped
+///
+/// Connections are handled one at a time, so a client that connects and
+/// never writes would otherwise block every launch after it.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Largest forwarded argument line accepted, in bytes
+const MAX_LINE_LEN: u64 = 1024 * 1024;
+
+/// Something a launch asked the running instance to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// Bring the main window to the front
+    ShowMain,
+    /// Open the quick-capture window
+    Capture,
+    /// Open a draft in the main window
+    OpenDraft(String),
+    /// Follow a `dashtext://` link
+    Link(DeepLink),
+    /// Switch to a profile, then bring the main window to the front
+    SwitchProfile(String),
+}
+
+impl Request {
+    /// Parse launch arguments (without the program name)
+    ///
+    /// `open capture`, `open draft <uuid>` and `dashtext://` links are
+    /// honoured; `--background` asks for nothing, `--profile` switches
+    /// profiles, and anything else just shows the main window.
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let cli = match Cli::try_parse_args(args) {
+            Ok(cli) => cli,
+            Err(e) => {
+                tracing::warn!("Unrecognized arguments {:?}: {}", args, e);
+                return Some(Request::ShowMain);
+            }
+        };
+
+        if let Some(link) = cli.link {
+            return Some(match DeepLink::parse(&link) {
+                Ok(link) => Request::Link(link),
+                Err(e) => {
+                    tracing::warn!("Ignoring link: {}", e);
+                    Request::ShowMain
+                }
+            });
+        }
+
+        match cli.command {
+            Some(Command::Open {
+                target: Some(OpenTarget::Capture),
+            }) => Some(Request::Capture),
+            Some(Command::Open {
+                target: Some(OpenTarget::Draft { uuid }),
+            }) => Some(Request::OpenDraft(uuid)),
+            None if cli.background => None,
+            None if cli.profile.is_some() => cli.profile.map(Request::SwitchProfile),
+            _ => Some(Request::ShowMain),
+        }
+    }
+
+    /// Carry out the request in the running instance
+    pub fn dispatch(&self, app: &AppHandle) {
+        let result = match self {
+            Request::ShowMain => windows::show_main(app),
+            Request::Capture => {
+                windows::show_capture(app);
+                Ok(())
+            }
+            Request::OpenDraft(uuid) => windows::open_draft(app, uuid),
+            Request::Link(link) => {
+                // Links may touch the database, so they finish in the background
+                let app = app.clone();
+                let link = link.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = link.dispatch(&app).await {
+                        tracing::warn!("Failed to handle {:?}: {}", link, e);
+                    }
+                });
+                Ok(())
+            }
+            Request::SwitchProfile(name) => {
+                let app = app.clone();
+                let name = name.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = profiles::switch(&app, &name).await {
+                        tracing::warn!("Failed to switch to profile {}: {}", name, e);
+                    }
+                    if let Err(e) = windows::show_main(&app) {
+                        tracing::warn!("{}", e);
+                    }
+                });
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to handle {:?}: {}", self, e);
+        }
+    }
+}
+
+/// Outcome of trying to become the primary instance
+pub enum Launch {
+    /// This process owns the instance socket
+    Primary(Primary),
+    /// Arguments were handed to the running instance; this process should exit
+    Forwarded,
+    /// The socket couldn't be set up; run without single-instance enforcement
+    Standalone,
+}
+
+/// Ownership of the instance socket, held by the first instance
+pub struct Primary {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+/// Request from this process's own arguments, dispatched once the main window has loaded
+pub struct PendingRequest(Mutex<Option<Request>>);
+
+impl PendingRequest {
+    pub fn new(request: Option<Request>) -> Self {
+        Self(Mutex::new(request))
+    }
+
+    /// Dispatch the pending request, if it hasn't been already
+    pub fn dispatch(app: &AppHandle) {
+        let pending = app
+            .try_state::<PendingRequest>()
+            .and_then(|state| state.0.lock().unwrap().take());
+
+        if let Some(request) = pending {
+            request.dispatch(app);
+        }
+    }
+}
+
+/// Path of the instance socket
+fn socket_path() -> PathBuf {
+    paths::runtime_dir().join("dashtext.sock")
+}
+
+/// Become the primary instance, or forward `args` to the running one
+pub fn acquire(args: &[String]) -> Launch {
+    let path = socket_path();
+
+    // Two attempts: if binding races with another launch, forward to the winner
+    for _ in 0..2 {
+        match forward(&path, args) {
+            Ok(()) => {
+                tracing::info!("Forwarded arguments to running instance");
+                return Launch::Forwarded;
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                // No instance running; clear a stale socket from a crashed run
+                let _ = fs::remove_file(&path);
+            }
+            Err(e) => tracing::warn!("Failed to reach running instance: {}", e),
+        }
+
+        match UnixListener::bind(&path) {
+            Ok(listener) => {
+                let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+                return Launch::Primary(Primary { listener, path });
+            }
+            Err(e) if e.kind() == ErrorKind::AddrInUse => continue,
+            Err(e) => {
+                // Run without single-instance support rather than not at all
+                tracing::warn!("Failed to bind instance socket {:?}: {}", path, e);
+                break;
+            }
+        }
+    }
+
+    tracing::warn!("Running without single-instance enforcement");
+    Launch::Standalone
+}
+
+/// Send arguments to the running instance
+fn forward(path: &Path, args: &[String]) -> std::io::Result<()> {
+    let mut stream = UnixStream::connect(path)?;
+    let mut line = serde_json::to_string(args)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+impl Primary {
+    /// Accept forwarded arguments on a background thread for the life of the app
+    pub fn serve(self, app: AppHandle) {
+        tracing::info!("Listening for other instances on {:?}", self.path);
+
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                let stream = match stream {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept instance connection: {}", e);
+                        continue;
+                    }
+                };
+
+                match read_args(stream, READ_TIMEOUT) {
+                    Ok(args) => {
+                        if let Some(request) = Request::from_args(&args) {
+                            request.dispatch(&app);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Ignoring forwarded arguments: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Read one line of JSON-encoded arguments, giving up after `timeout`
+fn read_args(stream: UnixStream, timeout: Duration) -> Result<Vec<String>, String> {
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| format!("Failed to set read timeout: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_LINE_LEN))
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read: {}", e))?;
+    serde_json::from_str(&line).map_err(|e| format!("Malformed arguments: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_forwarded_arguments() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        client.write_all(b"[\"open\",\"capture\"]\n").unwrap();
+        assert_eq!(
+            read_args(server, READ_TIMEOUT),
+            Ok(args(&["open", "capture"]))
+        );
+    }
+
+    #[test]
+    fn gives_up_on_silent_clients() {
+        let (_client, server) = UnixStream::pair().unwrap();
+        let started = Instant::now();
+        assert!(read_args(server, Duration::from_millis(50)).is_err());
+        assert!(started.elapsed() < READ_TIMEOUT);
+    }
+
+    #[test]
+    fn rejects_malformed_arguments() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        client.write_all(b"{\"open\": 1}\n").unwrap();
+        assert!(read_args(server, READ_TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn parses_requests() {
+        let uuid = "0b7e7d5c-3c39-4c8e-9a4e-3f0f6a8e2d11";
+        assert_eq!(Request::from_args(&[]), Some(Request::ShowMain));
+        assert_eq!(
+            Request::from_args(&args(&["open", "capture"])),
+            Some(Request::Capture)
+        );
+        assert_eq!(
+            Request::from_args(&args(&["open", "draft", uuid])),
+            Some(Request::OpenDraft(uuid.to_string()))
+        );
+        assert_eq!(
+            Request::from_args(&args(&["--profile", "work"])),
+            Some(Request::SwitchProfile("work".to_string()))
+        );
+        assert_eq!(Request::from_args(&args(&["--background"])), None);
+        assert_eq!(
+            Request::from_args(&args(&["--no-such-flag"])),
+            Some(Request::ShowMain)
+        );
+    }
+
+    #[test]
+    fn parses_links() {
+        assert_eq!(
+            Request::from_args(&args(&["dashtext://capture"])),
+            Some(Request::Link(DeepLink::Capture {
+                text: None,
+                tags: Vec::new()
+            }))
+        );
+        assert_eq!(
+            Request::from_args(&args(&["dashtext://nowhere"])),
+            Some(Request::ShowMain)
+        );
+    }
+}
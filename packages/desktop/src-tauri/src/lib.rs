@@ -1,8 +1,31 @@
+mod background;
+mod capture;
+pub mod cli;
 mod conf;
+mod crash;
 mod db;
+mod dbus;
+mod deeplink;
+mod desktop_entry;
+mod diagnostics;
+mod drafts;
+mod geometry;
 mod hotkey;
+mod instance;
+mod logs;
+mod notify;
+mod paths;
+mod profiles;
+mod rpc;
+mod shutdown;
+#[cfg(test)]
+mod testing;
+mod trace;
+mod tray;
 mod updater;
+mod windows;
 
+use tauri::webview::PageLoadEvent;
 use tauri::Manager;
 
 /// Register capture shortcut hotkey
@@ -22,30 +45,386 @@ async fn unregister_capture_shortcut(
     state.unregister_capture_shortcut().await
 }
 
+/// Register silent capture shortcut hotkey
+#[tauri::command]
+async fn register_silent_capture_shortcut(
+    shortcut: String,
+    inbox_uuid: Option<String>,
+    state: tauri::State<'_, conf::SettingsState>,
+) -> Result<(), String> {
+    state
+        .register_silent_capture_shortcut(&shortcut, inbox_uuid)
+        .await
+}
+
+/// Unregister silent capture shortcut hotkey
+#[tauri::command]
+async fn unregister_silent_capture_shortcut(
+    state: tauri::State<'_, conf::SettingsState>,
+) -> Result<(), String> {
+    state.unregister_silent_capture_shortcut().await
+}
+
+/// Enable global text expansion
+#[tauri::command]
+async fn enable_text_expansion(
+    exclusions: Vec<String>,
+    state: tauri::State<'_, conf::SettingsState>,
+) -> Result<(), String> {
+    state.enable_text_expansion(exclusions).await
+}
+
+/// Disable global text expansion
+#[tauri::command]
+async fn disable_text_expansion(
+    state: tauri::State<'_, conf::SettingsState>,
+) -> Result<(), String> {
+    state.disable_text_expansion().await
+}
+
+/// Pause or resume global text expansion
+#[tauri::command]
+async fn set_text_expansion_paused(
+    paused: bool,
+    state: tauri::State<'_, conf::SettingsState>,
+) -> Result<(), String> {
+    state.set_text_expansion_paused(paused).await
+}
+
+/// Open the quick-capture window
+#[tauri::command]
+fn show_capture(app: tauri::AppHandle) {
+    windows::show_capture(&app);
+}
+
+/// Where the capture window appears when shown
+#[tauri::command]
+fn get_capture_placement(
+    state: tauri::State<'_, geometry::GeometryState>,
+) -> geometry::CapturePlacement {
+    state.capture_placement()
+}
+
+/// Set where the capture window appears when shown
+#[tauri::command]
+fn set_capture_placement(
+    placement: geometry::CapturePlacement,
+    state: tauri::State<'_, geometry::GeometryState>,
+) -> Result<(), String> {
+    state.set_capture_placement(placement)
+}
+
+/// Whether each notification category is enabled
+#[tauri::command]
+fn get_notification_settings(
+    state: tauri::State<'_, notify::NotificationState>,
+) -> std::collections::HashMap<notify::Category, bool> {
+    state.settings()
+}
+
+/// Turn a notification category on or off
+#[tauri::command]
+fn set_notification_enabled(
+    category: notify::Category,
+    enabled: bool,
+    state: tauri::State<'_, notify::NotificationState>,
+) -> Result<(), String> {
+    state.set_enabled(category, enabled)
+}
+
+/// Write a diagnostics bundle for bug reports, returning its path
+///
+/// Goes to the downloads folder unless `directory` is given.
+#[tauri::command]
+async fn create_diagnostics_bundle(
+    app: tauri::AppHandle,
+    directory: Option<String>,
+) -> Result<String, String> {
+    diagnostics::create_bundle(&app, directory.as_deref())
+        .await
+        .map(|path| path.display().to_string())
+}
+
+/// The newest crash report that hasn't been shown yet
+#[tauri::command]
+fn get_unseen_crash_report() -> Result<Option<crash::CrashReport>, String> {
+    crash::unseen_report()
+}
+
+/// Stop offering a crash report on later starts
+#[tauri::command]
+fn mark_crash_report_seen(name: String) -> Result<(), String> {
+    crash::mark_seen(&name)
+}
+
+/// Delete a crash report
+#[tauri::command]
+fn delete_crash_report(name: String) -> Result<(), String> {
+    crash::delete(&name)
+}
+
+/// Current log level directives
+#[tauri::command]
+fn get_log_levels() -> String {
+    logs::levels()
+}
+
+/// Change log levels, e.g. `info,hotkey=debug`, and save them for later starts
+#[tauri::command]
+fn set_log_levels(levels: String) -> Result<(), String> {
+    logs::set_levels(&levels)
+}
+
+/// Open the current log file in the default viewer
+#[tauri::command]
+fn open_log(app: tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+    let path = logs::log_path()?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))
+}
+
+/// Copy the current log file to `destination`
+#[tauri::command]
+fn export_log(destination: String) -> Result<(), String> {
+    logs::export(&destination)
+}
+
+/// Whether the main window should stay hidden because the app was launched with `--background`
+#[tauri::command]
+fn take_background_launch(state: tauri::State<'_, background::BackgroundState>) -> bool {
+    state.take_launched_hidden()
+}
+
+/// Whether the app starts in the background on login
+#[tauri::command]
+fn is_autostart_enabled() -> bool {
+    background::is_autostart_enabled()
+}
+
+/// Install or remove the XDG autostart entry
+#[tauri::command]
+fn set_autostart(enabled: bool) -> Result<(), String> {
+    background::set_autostart(enabled)
+}
+
+/// Name of the current profile
+#[tauri::command]
+fn get_profile() -> String {
+    profiles::current()
+}
+
+/// Names of all profiles, default first
+#[tauri::command]
+fn list_profiles() -> Vec<String> {
+    profiles::list()
+}
+
+/// Switch to a profile, creating it if needed; windows reload once it's open
+#[tauri::command]
+async fn switch_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    profiles::switch(&app, &name).await
+}
+
+/// SQL plugin URL of the current profile's database
+#[tauri::command]
+fn get_database_url() -> String {
+    db::url()
+}
+
+/// Register the app as the handler for `dashtext://` links
+#[tauri::command]
+fn register_url_handler() -> Result<String, String> {
+    desktop_entry::register_url_handler().map(|path| path.display().to_string())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_log::Builder::new().build())
+    crash::install_hook();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let primary = match instance::acquire(&args) {
+        instance::Launch::Primary(primary) => Some(primary),
+        instance::Launch::Forwarded => return,
+        instance::Launch::Standalone => None,
+    };
+    // The main window shows itself on a plain launch; only explicit requests need handling
+    let pending = if args.is_empty() {
+        None
+    } else {
+        instance::Request::from_args(&args)
+    };
+    let cli = cli::Cli::try_parse_args(&args).ok();
+    let background = cli.as_ref().is_some_and(|cli| cli.background);
+
+    // Start tracing before anything worth measuring
+    if let Some(path) = cli.as_ref().and_then(|cli| cli.trace.clone()) {
+        match trace::start(path) {
+            Ok(path) => eprintln!("dashtext: writing trace to {}", path.display()),
+            Err(e) => eprintln!("dashtext: {}", e),
+        }
+    }
+    let startup = tracing::debug_span!("startup").entered();
+
+    // Before the SQL plugin is built, since it migrates the profile's database
+    let profile = cli.and_then(|cli| cli.profile);
+    if let Err(e) = profiles::select(profile.as_deref()).and_then(|_| profiles::lock_current()) {
+        eprintln!("dashtext: {}", e);
+        std::process::exit(1);
+    }
+
+    let builder = tauri::Builder::default()
+        .plugin(logs::plugin())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(db::plugin())
-        .setup(|app| {
+        .setup(move |app| {
+            let _setup = tracing::debug_span!("setup").entered();
+            logs::init();
+            app.manage(notify::NotificationState::load());
+            app.manage(drafts::trash::TrashState::load());
+            app.manage(shutdown::ShutdownState::default());
+            app.manage(windows::PendingShow::default());
+            shutdown::handle_signals(app.handle().clone());
+
             // Initialize settings state
             let settings_state = conf::SettingsState::new(app.handle().clone());
             app.manage(settings_state);
+            app.manage(background::BackgroundState::new(background));
+
+            // Migrate now rather than when the frontend loads the database, so it's
+            // measured and Rust code can use the database from the start
+            let url = db::url();
+            let opened = tauri::async_runtime::block_on(async {
+                let pool = db::open(&url).await?;
+                db::install(app.handle(), &url, pool).await
+            });
+            if let Err(e) = opened {
+                tracing::warn!("{}", e);
+            }
+
+            // Apply the revision retention policy without holding up startup
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let pruned = match db::pool(&handle).await {
+                    Ok(pool) => drafts::revisions::prune_all(&pool).await,
+                    Err(e) => Err(e),
+                };
+                match pruned {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Pruned {} old draft revisions", count),
+                    Err(e) => tracing::warn!("{}", e),
+                }
+            });
+            drafts::trash::schedule(app.handle().clone());
+
+            // Restore the main window before the frontend shows it
+            let geometry = geometry::GeometryState::load();
+            if let Some(main) = app.get_webview_window(windows::MAIN) {
+                geometry.restore(&main);
+            }
+            app.manage(geometry);
+
+            // Accept arguments from later launches, and handle our own once the UI is up
+            if let Some(primary) = primary {
+                primary.serve(app.handle().clone());
+                rpc::serve(app.handle().clone());
+                dbus::serve(app.handle().clone());
+            }
+            app.manage(instance::PendingRequest::new(pending));
+
+            if let Err(e) = windows::create_capture(app.handle()) {
+                tracing::warn!("{}", e);
+            }
+
+            // A missing tray (e.g. no StatusNotifier host) shouldn't stop the app
+            match tracing::debug_span!("tray").in_scope(|| tray::create(app.handle())) {
+                // The tray can bring the main window back, so closing it can just hide it
+                Ok(()) => app
+                    .state::<background::BackgroundState>()
+                    .set_hide_on_close(true),
+                Err(e) => tracing::warn!("Failed to create tray icon: {}", e),
+            }
 
             // Note: Hotkey will be registered by frontend after loading settings from database
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Geometry is recorded before close handlers hide the window
+            if let Some(state) = window.try_state::<geometry::GeometryState>() {
+                state.on_window_event(window, event);
+            }
+            windows::on_window_event(window, event);
+            if let Some(state) = window.try_state::<background::BackgroundState>() {
+                state.on_window_event(window, event);
+            }
+        })
+        .on_page_load(|webview, payload| {
+            if webview.label() == windows::MAIN && payload.event() == PageLoadEvent::Finished {
+                instance::PendingRequest::dispatch(webview.app_handle());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             updater::check_update,
             updater::can_auto_update,
             updater::download_and_install_update,
             updater::restart_app,
             updater::get_current_version,
+            drafts::commands::list_drafts,
+            drafts::commands::get_draft,
+            drafts::commands::search_drafts,
+            drafts::commands::create_draft,
+            drafts::commands::save_draft,
+            drafts::commands::archive_draft,
+            drafts::commands::unarchive_draft,
+            drafts::commands::pin_draft,
+            drafts::commands::unpin_draft,
+            drafts::commands::delete_draft,
+            drafts::commands::restore_draft,
+            drafts::commands::hard_delete_draft,
+            drafts::commands::list_revisions,
+            drafts::commands::get_revision,
+            drafts::commands::diff_revision,
+            drafts::commands::restore_revision,
+            drafts::commands::list_trash,
+            drafts::commands::empty_trash,
+            drafts::commands::get_trash_settings,
+            drafts::commands::set_trash_retention,
             register_capture_shortcut,
             unregister_capture_shortcut,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+            register_silent_capture_shortcut,
+            unregister_silent_capture_shortcut,
+            enable_text_expansion,
+            disable_text_expansion,
+            set_text_expansion_paused,
+            show_capture,
+            get_capture_placement,
+            get_notification_settings,
+            get_log_levels,
+            set_log_levels,
+            open_log,
+            export_log,
+            create_diagnostics_bundle,
+            get_profile,
+            list_profiles,
+            switch_profile,
+            get_database_url,
+            get_unseen_crash_report,
+            mark_crash_report_seen,
+            delete_crash_report,
+            set_notification_enabled,
+            set_capture_placement,
+            register_url_handler,
+            take_background_launch,
+            is_autostart_enabled,
+            set_autostart,
+        ]);
+
+    // Plugins are initialized and `setup` runs while building
+    let app = tracing::debug_span!("build")
+        .in_scope(|| builder.build(tauri::generate_context!()))
+        .expect("error while building tauri application");
+    drop(startup);
+    app.run(|app, event| shutdown::on_run_event(app, &event));
 }
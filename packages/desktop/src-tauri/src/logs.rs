@@ -0,0 +1,260 @@
+//! Log files and levels.
+//!
+//! Logs go to `dashtext.log` in [`paths::log_dir`], rotated at
+//! [`MAX_FILE_SIZE`] with the newest [`KEEP_FILES`] kept. Levels use
+//! `RUST_LOG`-style directives such as `info,hotkey=debug,updater=info`,
+//! where module names are relative to this crate. `DASHTEXT_LOG` overrides
+//! the setting saved in the profile's directory, and levels can be changed
+//! while the app runs.
+
+use crate::{crash, paths, profiles};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::plugin::TauriPlugin;
+use tauri::Runtime;
+use tauri_plugin_log::log::{self, LevelFilter, Metadata};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+/// Log file name, without the `.log` extension the plugin adds
+const FILE_NAME: &str = "dashtext";
+
+/// Size at which the log file is rotated
+const MAX_FILE_SIZE: u128 = 5 * 1024 * 1024;
+
+/// Number of rotated log files kept
+const KEEP_FILES: usize = 5;
+
+/// Environment variable that overrides the saved levels
+const ENV_VAR: &str = "DASHTEXT_LOG";
+
+/// Setting file inside the profile's directory
+const SETTING_FILE: &str = "log-levels";
+
+/// Levels used when nothing is configured
+const DEFAULT_LEVELS: &str = "info";
+
+/// Prefix stripped from targets, so directives can name modules like `hotkey`
+const CRATE_PREFIX: &str = "dashtext_lib::";
+
+static LEVELS: RwLock<Option<Levels>> = RwLock::new(None);
+
+/// Parsed level directives
+#[derive(Debug, Clone)]
+struct Levels {
+    spec: String,
+    default: LevelFilter,
+    /// Module-specific levels, longest module first so the most specific wins
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Levels {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut default = LevelFilter::Info;
+        let mut modules = Vec::new();
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    modules.push((module.trim().to_string(), parse_level(level)?));
+                }
+                None => default = parse_level(directive)?,
+            }
+        }
+        modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+
+        Ok(Self {
+            spec: spec.trim().to_string(),
+            default,
+            modules,
+        })
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let target = target.strip_prefix(CRATE_PREFIX).unwrap_or(target);
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid log level: {}", level.trim()))
+}
+
+/// Path of the current log file
+pub fn log_path() -> Result<PathBuf, String> {
+    Ok(paths::log_dir()?.join(format!("{}.log", FILE_NAME)))
+}
+
+fn setting_path() -> Result<PathBuf, String> {
+    Ok(profiles::dir(&profiles::current())?.join(SETTING_FILE))
+}
+
+/// Levels from `DASHTEXT_LOG`, the current profile's setting, or the default
+fn configured_levels() -> Levels {
+    let spec = std::env::var(ENV_VAR)
+        .ok()
+        .or_else(|| {
+            setting_path()
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+        })
+        .unwrap_or_else(|| DEFAULT_LEVELS.to_string());
+    Levels::parse(&spec).unwrap_or_else(|e| {
+        eprintln!("dashtext: {}; using {:?}", e, DEFAULT_LEVELS);
+        Levels::parse(DEFAULT_LEVELS).expect("default log levels are valid")
+    })
+}
+
+/// Returns the log plugin, writing to stdout, the log file and crash reports
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    *LEVELS.write().unwrap() = Some(configured_levels());
+
+    let mut targets = vec![Target::new(TargetKind::Stdout), crash::log_target()];
+    match paths::log_dir() {
+        Ok(path) => targets.push(Target::new(TargetKind::Folder {
+            path,
+            file_name: Some(FILE_NAME.to_string()),
+        })),
+        Err(e) => eprintln!("dashtext: {}; not writing a log file", e),
+    }
+
+    tauri_plugin_log::Builder::new()
+        .targets(targets)
+        .max_file_size(MAX_FILE_SIZE)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_FILES))
+        // Let everything through the plugin, so raising a level later takes effect
+        .level(LevelFilter::Trace)
+        .filter(enabled)
+        .build()
+}
+
+/// Skip formatting records no directive wants; call after the log plugin is set up
+pub fn init() {
+    if let Some(levels) = &*LEVELS.read().unwrap() {
+        log::set_max_level(levels.max_level());
+    }
+}
+
+/// Switch to the current profile's levels, after a profile switch
+pub fn reload() {
+    let levels = configured_levels();
+    log::set_max_level(levels.max_level());
+    *LEVELS.write().unwrap() = Some(levels);
+}
+
+fn enabled(metadata: &Metadata) -> bool {
+    match &*LEVELS.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(levels) => metadata.level() <= levels.level_for(metadata.target()),
+        None => true,
+    }
+}
+
+/// Current level directives
+pub fn levels() -> String {
+    LEVELS
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|levels| levels.spec.clone())
+        .unwrap_or_else(|| DEFAULT_LEVELS.to_string())
+}
+
+/// Change levels now and save them for later starts
+pub fn set_levels(spec: &str) -> Result<(), String> {
+    let levels = Levels::parse(spec)?;
+    log::set_max_level(levels.max_level());
+    *LEVELS.write().unwrap() = Some(levels);
+
+    let path = setting_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    fs::write(&path, spec.trim()).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    tracing::info!("Log levels set to {:?}", spec.trim());
+    Ok(())
+}
+
+/// Copy the current log file to `destination`
+pub fn export(destination: &str) -> Result<(), String> {
+    let source = log_path()?;
+    fs::copy(&source, destination)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to export {:?} to {}: {}", source, destination, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_default_and_module_levels() {
+        let levels = Levels::parse(" warn, hotkey=debug ,updater=info,").unwrap();
+        assert_eq!(levels.spec, "warn, hotkey=debug ,updater=info,");
+        assert_eq!(levels.default, LevelFilter::Warn);
+        assert_eq!(levels.level_for("dashtext_lib::hotkey"), LevelFilter::Debug);
+        assert_eq!(levels.level_for("dashtext_lib::updater"), LevelFilter::Info);
+        assert_eq!(levels.level_for("dashtext_lib::db"), LevelFilter::Warn);
+        assert_eq!(levels.max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn most_specific_module_wins() {
+        let levels = Levels::parse("info,hotkey=warn,hotkey::evdev=trace").unwrap();
+        assert_eq!(
+            levels.level_for("dashtext_lib::hotkey::evdev"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            levels.level_for("dashtext_lib::hotkey::global"),
+            LevelFilter::Warn
+        );
+        // Only whole module names match
+        assert_eq!(levels.level_for("dashtext_lib::hotkeys"), LevelFilter::Info);
+        // Other crates' targets are matched as-is
+        assert_eq!(levels.level_for("zbus::connection"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn empty_spec_uses_info() {
+        let levels = Levels::parse("").unwrap();
+        assert_eq!(levels.default, LevelFilter::Info);
+        assert_eq!(levels.max_level(), LevelFilter::Info);
+        assert_eq!(
+            Levels::parse(DEFAULT_LEVELS).unwrap().default,
+            LevelFilter::Info
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_levels() {
+        assert_eq!(
+            Levels::parse("loud").unwrap_err(),
+            "Invalid log level: loud"
+        );
+        assert_eq!(
+            Levels::parse("info,hotkey= verbose").unwrap_err(),
+            "Invalid log level: verbose"
+        );
+    }
+}
@@ -1,6 +1,9 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use clap::Parser;
+use dashtext_lib::cli::Cli;
+
 fn main() {
     // Handle --version before Tauri starts
     let args: Vec<String> = std::env::args().collect();
@@ -9,5 +12,11 @@ fn main() {
         std::process::exit(0);
     }
 
+    // Headless subcommands exit without starting the webview
+    let cli = Cli::parse().checked().unwrap_or_else(|e| e.exit());
+    if let Some(code) = dashtext_lib::cli::run_headless(&cli) {
+        std::process::exit(code);
+    }
+
     dashtext_lib::run();
 }
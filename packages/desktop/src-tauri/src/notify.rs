@@ -0,0 +1,536 @@
+//! Desktop notifications through `org.freedesktop.Notifications`.
+//!
+//! Used for events that happen while no window may be open: silent
+//! captures, hotkey registration failures and available updates. Each
+//! category can be turned off; the choice is saved to `notifications.json`
+//! in the profile's directory.
+//!
+//! The session bus is only connected on the first notification, so pointing
+//! `DBUS_SESSION_BUS_ADDRESS` at a private bus with a stub notification
+//! service is enough to exercise this module without a desktop.
+
+use crate::{profiles, tray, windows};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::OnceCell;
+use zbus::zvariant::Value;
+use zbus::Connection;
+
+/// Settings file name inside the app config directory
+const FILE_NAME: &str = "notifications.json";
+
+/// Let the notification server pick how long to show notifications
+const DEFAULT_TIMEOUT: i32 = -1;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Kinds of notification, each with its own toggle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// A silent capture was saved, or failed
+    Capture,
+    /// A hotkey couldn't be registered
+    Hotkey,
+    /// A newer version is available
+    Update,
+}
+
+impl Category {
+    const ALL: [Category; 3] = [Category::Capture, Category::Hotkey, Category::Update];
+
+    /// Vendor `category` hint, so notification servers can group or filter ours
+    fn hint(&self) -> &'static str {
+        match self {
+            Category::Capture => "x-dashtext.capture",
+            Category::Hotkey => "x-dashtext.hotkey",
+            Category::Update => "x-dashtext.update",
+        }
+    }
+}
+
+/// A button on a notification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Open a draft in the main window
+    OpenDraft(String),
+    /// Show the update dialog
+    InstallUpdate,
+}
+
+impl Action {
+    fn key(&self) -> &'static str {
+        match self {
+            Action::OpenDraft(_) => "open-draft",
+            Action::InstallUpdate => "install-update",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Action::OpenDraft(_) => "Open draft",
+            Action::InstallUpdate => "Install update",
+        }
+    }
+
+    fn invoke<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        match self {
+            Action::OpenDraft(uuid) => windows::open_draft(app, uuid),
+            Action::InstallUpdate => windows::show_main(app).and_then(|_| {
+                app.emit_to(windows::MAIN, tray::CHECK_UPDATE_EVENT, ())
+                    .map_err(|e| format!("Failed to show update dialog: {}", e))
+            }),
+        }
+    }
+}
+
+/// A notification to show
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub category: Category,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<Action>,
+}
+
+impl Notification {
+    pub fn new(category: Category, summary: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            category,
+            summary: summary.into(),
+            body: body.into(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+}
+
+/// Settings file of the current profile
+fn settings_path() -> Option<PathBuf> {
+    profiles::dir(&profiles::current())
+        .map(|dir| dir.join(FILE_NAME))
+        .ok()
+}
+
+/// Categories turned off in the settings file at `path`
+fn read_disabled(path: Option<&PathBuf>) -> Vec<Category> {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str::<HashMap<Category, bool>>(&json).ok())
+        .map(|settings| {
+            settings
+                .into_iter()
+                .filter(|(_, enabled)| !enabled)
+                .map(|(category, _)| category)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Notification settings and the bus connection they're sent over
+pub struct NotificationState {
+    path: Mutex<Option<PathBuf>>,
+    disabled: Mutex<Vec<Category>>,
+    connection: OnceCell<Connection>,
+    /// Actions of notifications still on screen, by notification ID
+    pending: Arc<Mutex<HashMap<u32, Vec<Action>>>>,
+    /// Version last announced, so hourly update checks don't repeat themselves
+    announced_version: Mutex<Option<String>>,
+}
+
+impl NotificationState {
+    /// Load settings, with every category enabled if none are saved
+    pub fn load() -> Self {
+        Self::open(settings_path())
+    }
+
+    /// Load settings from `path`, or keep them in memory only if it's `None`
+    fn open(path: Option<PathBuf>) -> Self {
+        Self {
+            disabled: Mutex::new(read_disabled(path.as_ref())),
+            path: Mutex::new(path),
+            connection: OnceCell::new(),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            announced_version: Mutex::new(None),
+        }
+    }
+
+    /// Whether each category is enabled
+    pub fn settings(&self) -> HashMap<Category, bool> {
+        let disabled = self.disabled.lock().unwrap();
+        Category::ALL
+            .into_iter()
+            .map(|category| (category, !disabled.contains(&category)))
+            .collect()
+    }
+
+    /// Turn a category on or off
+    pub fn set_enabled(&self, category: Category, enabled: bool) -> Result<(), String> {
+        {
+            let mut disabled = self.disabled.lock().unwrap();
+            disabled.retain(|c| *c != category);
+            if !enabled {
+                disabled.push(category);
+            }
+        }
+
+        let Some(path) = self.path.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.settings())
+            .map_err(|e| format!("Failed to serialize notification settings: {}", e))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+
+    /// Switch to the current profile's settings, after a profile switch
+    pub fn reload(&self) {
+        let path = settings_path();
+        *self.disabled.lock().unwrap() = read_disabled(path.as_ref());
+        *self.path.lock().unwrap() = path;
+    }
+
+    /// Show a notification, returning its ID, or `None` if its category is off
+    pub async fn show<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        notification: &Notification,
+    ) -> Result<Option<u32>, String> {
+        if self.disabled.lock().unwrap().contains(&notification.category) {
+            return Ok(None);
+        }
+
+        let connection = self
+            .connection
+            .get_or_try_init(|| async {
+                let connection = Connection::session().await?;
+                self.listen_for_actions(app.clone(), connection.clone());
+                Ok::<_, zbus::Error>(connection)
+            })
+            .await
+            .map_err(|e| format!("Failed to connect to session bus: {}", e))?;
+
+        let mut actions = Vec::new();
+        for action in &notification.actions {
+            actions.extend([action.key(), action.label()]);
+        }
+        // Clicking the notification itself does the first action
+        if let Some(first) = notification.actions.first() {
+            actions.extend(["default", first.label()]);
+        }
+
+        let hints = HashMap::from([
+            ("desktop-entry", Value::from("dashtext")),
+            ("category", Value::from(notification.category.hint())),
+        ]);
+        let proxy = NotificationsProxy::new(connection)
+            .await
+            .map_err(|e| format!("Failed to reach notification service: {}", e))?;
+        let id = proxy
+            .notify(
+                "DashText",
+                0,
+                "dashtext",
+                &notification.summary,
+                &notification.body,
+                &actions,
+                hints,
+                DEFAULT_TIMEOUT,
+            )
+            .await
+            .map_err(|e| format!("Failed to send notification: {}", e))?;
+
+        if !notification.actions.is_empty() {
+            self.pending
+                .lock()
+                .unwrap()
+                .insert(id, notification.actions.clone());
+        }
+        Ok(Some(id))
+    }
+
+    /// Whether `version` hasn't been announced yet, marking it as announced
+    pub fn first_announcement(&self, version: &str) -> bool {
+        let mut announced = self.announced_version.lock().unwrap();
+        if announced.as_deref() == Some(version) {
+            return false;
+        }
+        *announced = Some(version.to_string());
+        true
+    }
+
+    /// Run actions the user clicks, and forget notifications once they close
+    fn listen_for_actions<R: Runtime>(&self, app: AppHandle<R>, connection: Connection) {
+        let pending = self.pending.clone();
+        tauri::async_runtime::spawn(async move {
+            let result: zbus::Result<()> = async {
+                let proxy = NotificationsProxy::new(&connection).await?;
+                let mut invoked = proxy.receive_action_invoked().await?;
+                let mut closed = proxy.receive_notification_closed().await?;
+
+                loop {
+                    tokio::select! {
+                        Some(signal) = invoked.next() => {
+                            let args = signal.args()?;
+                            let actions = pending.lock().unwrap().remove(&args.id);
+                            let action = actions.and_then(|actions| {
+                                actions.into_iter().enumerate().find_map(|(i, action)| {
+                                    let matches = action.key() == args.action_key
+                                        || (i == 0 && args.action_key == "default");
+                                    matches.then_some(action)
+                                })
+                            });
+                            if let Some(action) = action
+                                && let Err(e) = action.invoke(&app)
+                            {
+                                tracing::warn!("Failed to handle notification action: {}", e);
+                            }
+                        }
+                        Some(signal) = closed.next() => {
+                            pending.lock().unwrap().remove(&signal.args()?.id);
+                        }
+                        else => return Ok(()),
+                    }
+                }
+            }
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!("Stopped listening for notification actions: {}", e);
+            }
+        });
+    }
+}
+
+/// Show a notification in the background, logging failures
+pub fn send(app: &AppHandle, notification: Notification) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<NotificationState>() else {
+            return;
+        };
+        if let Err(e) = state.show(&app, &notification).await {
+            tracing::warn!("{}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestBus;
+    use std::time::Duration;
+    use tauri::{Listener, WebviewUrl, WebviewWindowBuilder};
+    use tokio::sync::mpsc;
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::OwnedValue;
+
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    /// A `Notify` call the stub received
+    struct Sent {
+        summary: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+    }
+
+    /// Notification server that records notifications instead of showing them
+    #[derive(Default)]
+    struct StubServer {
+        sent: Arc<Mutex<Vec<Sent>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl StubServer {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            _body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(Sent {
+                summary,
+                actions,
+                hints,
+            });
+            sent.len() as u32
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            action_key: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    fn hint(sent: &Sent, key: &str) -> String {
+        String::try_from(sent.hints[key].try_clone().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn saves_each_toggle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profile").join(FILE_NAME);
+        let state = NotificationState::open(Some(path.clone()));
+        assert!(state.settings().values().all(|enabled| *enabled));
+
+        // Turning a category off twice still takes one switch to turn it back on
+        state.set_enabled(Category::Hotkey, false).unwrap();
+        state.set_enabled(Category::Hotkey, false).unwrap();
+        let saved = NotificationState::open(Some(path.clone())).settings();
+        assert!(!saved[&Category::Hotkey]);
+        assert!(saved[&Category::Capture] && saved[&Category::Update]);
+
+        state.set_enabled(Category::Hotkey, true).unwrap();
+        assert_eq!(read_disabled(Some(&path)), []);
+    }
+
+    #[test]
+    fn reads_missing_or_malformed_settings_as_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        assert_eq!(read_disabled(None), []);
+        assert_eq!(read_disabled(Some(&path)), []);
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(read_disabled(Some(&path)), []);
+
+        fs::write(&path, r#"{"update": false, "capture": true}"#).unwrap();
+        assert_eq!(read_disabled(Some(&path)), [Category::Update]);
+    }
+
+    #[test]
+    fn skips_disabled_categories_without_the_bus() {
+        let app = tauri::test::mock_app();
+        let state = NotificationState::open(None);
+        state.set_enabled(Category::Capture, false).unwrap();
+
+        let notification = Notification::new(Category::Capture, "Captured", "Saved");
+        let shown = tauri::async_runtime::block_on(state.show(app.handle(), &notification));
+        assert_eq!(shown, Ok(None));
+        assert!(state.connection.get().is_none());
+    }
+
+    #[test]
+    fn sends_hints_and_routes_actions() {
+        let bus = TestBus::start();
+
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            WebviewWindowBuilder::new(&app, windows::MAIN, WebviewUrl::default())
+                .build()
+                .unwrap();
+            let (opened_tx, mut opened) = mpsc::unbounded_channel();
+            app.listen_any(windows::OPEN_DRAFT_EVENT, move |event| {
+                let _ = opened_tx.send(event.payload().to_string());
+            });
+
+            let stub = StubServer::default();
+            let sent = stub.sent.clone();
+            let server = zbus::connection::Builder::address(bus.address.as_str())
+                .unwrap()
+                .name("org.freedesktop.Notifications")
+                .unwrap()
+                .serve_at(PATH, stub)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            let state = NotificationState::open(None);
+            let connection = zbus::connection::Builder::address(bus.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            state.connection.set(connection.clone()).unwrap();
+            state.listen_for_actions(app.handle().clone(), connection);
+
+            let notification = Notification::new(Category::Capture, "Captured", "Saved")
+                .action(Action::OpenDraft("draft-1".to_string()));
+            let id = state.show(app.handle(), &notification).await.unwrap();
+            let id = id.expect("capture notifications are on");
+
+            {
+                let sent = sent.lock().unwrap();
+                assert_eq!(sent.len(), 1);
+                assert_eq!(sent[0].summary, "Captured");
+                assert_eq!(
+                    sent[0].actions,
+                    ["open-draft", "Open draft", "default", "Open draft"]
+                );
+                assert_eq!(hint(&sent[0], "desktop-entry"), "dashtext");
+                assert_eq!(hint(&sent[0], "category"), "x-dashtext.capture");
+            }
+
+            // The listener subscribes in the background, so repeat the click until it's seen
+            let iface = server
+                .object_server()
+                .interface::<_, StubServer>(PATH)
+                .await
+                .unwrap();
+            let mut payload = None;
+            for _ in 0..50 {
+                StubServer::action_invoked(iface.signal_emitter(), id, "default")
+                    .await
+                    .unwrap();
+                if let Ok(Some(opened)) =
+                    tokio::time::timeout(Duration::from_millis(100), opened.recv()).await
+                {
+                    payload = Some(opened);
+                    break;
+                }
+            }
+            assert_eq!(payload.as_deref(), Some(r#""draft-1""#));
+            assert!(state.pending.lock().unwrap().is_empty());
+
+            state.set_enabled(Category::Update, false).unwrap();
+            let update = Notification::new(Category::Update, "Update", "1.0");
+            assert_eq!(state.show(app.handle(), &update).await.unwrap(), None);
+            assert_eq!(sent.lock().unwrap().len(), 1);
+        });
+    }
+}
@@ -0,0 +1,90 @@
+//! Filesystem locations, in one place so portable mode can move them.
+//!
+//! Normally files follow the XDG base directories. When a file named
+//! `portable` sits beside the executable, config, data and logs all go in a
+//! `dashtext-data` directory next to it instead, so the app can run from a
+//! USB stick or a synced folder. Sockets stay in the runtime directory either
+//! way, since removable filesystems often can't hold them.
+
+use std::fs;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Marker file beside the executable that turns on portable mode
+const PORTABLE_MARKER: &str = "portable";
+
+/// Directory beside the executable holding everything in portable mode
+const PORTABLE_DATA_DIR: &str = "dashtext-data";
+
+static PORTABLE_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Root of the portable data directory, if running in portable mode
+pub fn portable_root() -> Option<&'static Path> {
+    PORTABLE_ROOT
+        .get_or_init(|| {
+            let exe = std::env::current_exe().ok()?;
+            let dir = exe.parent()?;
+            dir.join(PORTABLE_MARKER)
+                .is_file()
+                .then(|| dir.join(PORTABLE_DATA_DIR))
+        })
+        .as_deref()
+}
+
+/// Whether data is kept beside the executable
+pub fn is_portable() -> bool {
+    portable_root().is_some()
+}
+
+/// Tauri's app config directory, or `config/` in portable mode
+///
+/// Holds the database and settings files.
+pub fn app_config_dir() -> Result<PathBuf, String> {
+    if let Some(root) = portable_root() {
+        return Ok(root.join("config"));
+    }
+    dirs::config_dir()
+        .map(|dir| dir.join("app.dashtext"))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+/// Directory for crash reports and the update lock, or `data/` in portable mode
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    if let Some(root) = portable_root() {
+        return Ok(root.join("data"));
+    }
+    dirs::data_local_dir()
+        .map(|dir| dir.join("dashtext"))
+        .ok_or_else(|| "Could not determine local data directory".to_string())
+}
+
+/// Directory log files are written to, or `logs/` in portable mode
+pub fn log_dir() -> Result<PathBuf, String> {
+    if let Some(root) = portable_root() {
+        return Ok(root.join("logs"));
+    }
+    dirs::state_dir()
+        .map(|dir| dir.join("dashtext"))
+        .ok_or_else(|| "Could not determine state directory".to_string())
+}
+
+/// Directory for sockets and other per-session files
+///
+/// This is `$XDG_RUNTIME_DIR` when set. Otherwise a private (0700) directory
+/// under the temp dir is used, so sockets are never reachable by other users.
+pub fn runtime_dir() -> PathBuf {
+    if let Some(dir) = dirs::runtime_dir() {
+        return dir;
+    }
+
+    let dir = std::env::temp_dir().join(format!("dashtext-{}", unsafe { libc::getuid() }));
+    match fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => {
+            tracing::warn!("Failed to create runtime directory {:?}: {}", dir, e);
+        }
+        _ => {}
+    }
+    let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
+    dir
+}
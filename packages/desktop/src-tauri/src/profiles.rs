@@ -0,0 +1,236 @@
+//! Named profiles, each with its own database and lock file.
+//!
+//! The `default` profile keeps the original `dashtext.db` in the app config
+//! directory; other profiles live in `profiles/<name>/`. A profile is picked
+//! with `--profile` at launch and can be switched while the app runs. Drafts
+//! and the settings table live in the profile's database, so each profile has
+//! its own hotkeys too. Settings kept in files, such as notifications, window
+//! geometry, log levels and trash retention, are in the profile's directory.
+
+use crate::conf::SettingsState;
+use crate::drafts::trash::TrashState;
+use crate::geometry::GeometryState;
+use crate::notify::NotificationState;
+use crate::{db, logs, paths, shutdown, tray};
+use fs2::FileExt;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Profile used when none is given
+pub const DEFAULT: &str = "default";
+
+/// Event emitted with the new profile's name after a switch
+pub const CHANGED_EVENT: &str = "profile:changed";
+
+/// Directory holding every profile except the default one
+const PROFILES_DIR: &str = "profiles";
+
+/// Lock file held while the app has a profile open
+const LOCK_FILE: &str = "dashtext.lock";
+
+/// Longest allowed profile name
+const MAX_NAME_LEN: usize = 32;
+
+static CURRENT: RwLock<String> = RwLock::new(String::new());
+
+/// Lock on the current profile, held by the app but not by headless commands
+static LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Check a profile name is safe to use as a directory name
+pub fn validate(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name {:?}: use up to {} lowercase letters, digits, '-' or '_'",
+            name, MAX_NAME_LEN
+        ))
+    }
+}
+
+/// Name of the current profile
+pub fn current() -> String {
+    let current = CURRENT.read().unwrap();
+    if current.is_empty() {
+        DEFAULT.to_string()
+    } else {
+        current.clone()
+    }
+}
+
+/// Make `name`, or the default profile, current for everything that follows
+pub fn select(name: Option<&str>) -> Result<(), String> {
+    let name = name.unwrap_or(DEFAULT);
+    validate(name)?;
+    *CURRENT.write().unwrap() = name.to_string();
+    Ok(())
+}
+
+/// Directory holding a profile's files
+pub fn dir(name: &str) -> Result<PathBuf, String> {
+    let config_dir = paths::app_config_dir()?;
+    if name == DEFAULT {
+        Ok(config_dir)
+    } else {
+        Ok(config_dir.join(PROFILES_DIR).join(name))
+    }
+}
+
+/// SQL plugin URL of a profile's database
+///
+/// URLs are relative to Tauri's app config directory, except in portable
+/// mode, where they point into the portable data directory instead.
+pub fn db_url(name: &str) -> String {
+    let relative = if name == DEFAULT {
+        "dashtext.db".to_string()
+    } else {
+        format!("{}/{}/dashtext.db", PROFILES_DIR, name)
+    };
+
+    if paths::is_portable()
+        && let Ok(dir) = paths::app_config_dir()
+    {
+        return format!("sqlite:{}", dir.join(relative).display());
+    }
+    format!("sqlite:{}", relative)
+}
+
+/// Names of all profiles that exist, default first
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = dir(DEFAULT)
+        .ok()
+        .and_then(|dir| fs::read_dir(dir.join(PROFILES_DIR)).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| validate(name).is_ok() && name != DEFAULT)
+        .collect();
+    names.sort();
+    names.insert(0, DEFAULT.to_string());
+    names
+}
+
+/// Lock a profile's directory, creating it if needed
+///
+/// Fails if another process has the profile open.
+fn acquire(name: &str) -> Result<File, String> {
+    let dir = dir(name)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+
+    let path = dir.join(LOCK_FILE);
+    let file = File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    file.try_lock_exclusive()
+        .map_err(|_| format!("Profile {} is open in another DashText process", name))?;
+    Ok(file)
+}
+
+/// Lock the current profile for the running app
+pub fn lock_current() -> Result<(), String> {
+    let file = acquire(&current())?;
+    *LOCK.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+/// Switch the running app to another profile, creating it if needed
+///
+/// Unsaved editor content is saved and hotkeys are unregistered before the
+/// old database is closed. Windows reload on [`CHANGED_EVENT`], which opens
+/// the new database and registers that profile's hotkeys.
+pub async fn switch(app: &AppHandle, name: &str) -> Result<(), String> {
+    validate(name)?;
+    if name == current() {
+        return Ok(());
+    }
+
+    let lock = acquire(name)?;
+    // Open before touching the current profile, so a failure leaves it open
+    let url = db_url(name);
+    let pool = db::open(&url).await?;
+
+    shutdown::flush_windows(app).await;
+    if let Some(settings) = app.try_state::<SettingsState>() {
+        settings.unregister_all().await;
+    }
+    if let Err(e) = db::close(app, &db::url()).await {
+        tracing::warn!("{}", e);
+    }
+
+    db::install(app, &url, pool).await?;
+    *CURRENT.write().unwrap() = name.to_string();
+    *LOCK.lock().unwrap() = Some(lock);
+    reload_settings(app);
+    tracing::info!("Switched to profile {}", name);
+
+    tray::refresh(app.clone());
+    app.emit(CHANGED_EVENT, name)
+        .map_err(|e| format!("Failed to announce profile change: {}", e))
+}
+
+/// Re-read the settings files of the now current profile
+fn reload_settings(app: &AppHandle) {
+    logs::reload();
+    if let Some(state) = app.try_state::<NotificationState>() {
+        state.reload();
+    }
+    if let Some(state) = app.try_state::<GeometryState>() {
+        state.reload();
+    }
+    if let Some(state) = app.try_state::<TrashState>() {
+        state.reload();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_simple_names() {
+        for name in [
+            "default",
+            "work",
+            "side-project_2",
+            &"a".repeat(MAX_NAME_LEN),
+        ] {
+            assert_eq!(validate(name), Ok(()), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_unsafe_names() {
+        for name in [
+            "",
+            "Work",
+            "..",
+            "a/b",
+            "a\\b",
+            "with space",
+            "émoji",
+            &"a".repeat(MAX_NAME_LEN + 1),
+        ] {
+            let error = validate(name).unwrap_err();
+            assert!(error.starts_with("Invalid profile name"), "{:?}", name);
+        }
+        assert!(select(Some("../escape")).is_err());
+    }
+
+    #[test]
+    fn keeps_other_profiles_apart() {
+        let config_dir = paths::app_config_dir().unwrap();
+        assert_eq!(dir(DEFAULT).unwrap(), config_dir);
+        assert_eq!(
+            dir("work").unwrap(),
+            config_dir.join(PROFILES_DIR).join("work")
+        );
+        assert!(db_url("work").ends_with("profiles/work/dashtext.db"));
+    }
+}
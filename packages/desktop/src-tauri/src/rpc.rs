@@ -0,0 +1,528 @@
+//! Local JSON-RPC control socket for editor plugins and scripts.
+//!
+//! The running app listens on `dashtext-rpc.sock` in the runtime directory and
+//! speaks JSON-RPC 2.0, one message per line. Access is limited by file
+//! permissions: the socket is created 0600 inside a per-user directory, and
+//! connections from other users are dropped.
+//!
+//! Methods:
+//! - `rpc.version` → `{ api_version, app_version }`
+//! - `drafts.list { archived?, deleted? }` → `[Draft]`
+//! - `drafts.get { uuid }` → `Draft | null`
+//! - `drafts.create { content? }` → `Draft`
+//! - `drafts.append { text, uuid? }` → `Draft` (defaults to the pinned draft)
+//! - `drafts.subscribe` → `true`, then `drafts.changed` notifications
+//! - `drafts.notify { uuid, kind, profile? }` → whether the change was announced,
+//!   for writes made outside the app, such as `dashtext capture`
+//! - `capture.open` → `null`
+//! - `window.show { draft? }` → `null`
+
+use crate::drafts::{self, ChangeKind, ListFilter};
+use crate::{db, paths, profiles, windows};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use tauri::{AppHandle, Listener, Runtime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast;
+
+/// Bumped on breaking changes to method names, params or results
+pub const API_VERSION: u32 = 1;
+
+/// Socket file name inside [`paths::runtime_dir`]
+const SOCKET_NAME: &str = "dashtext-rpc.sock";
+
+// Standard JSON-RPC error codes, plus one for application errors
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const APP_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(APP_ERROR, message)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListParams {
+    archived: bool,
+    deleted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetParams {
+    uuid: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CreateParams {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppendParams {
+    text: String,
+    #[serde(default)]
+    uuid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotifyParams {
+    uuid: String,
+    kind: ChangeKind,
+    /// Profile whose database changed; changes to other profiles are ignored
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ShowParams {
+    draft: Option<String>,
+}
+
+/// Start the control socket in the background
+pub fn serve(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app).await {
+            tracing::warn!("JSON-RPC socket stopped: {}", e);
+        }
+    });
+}
+
+/// Path of the control socket
+fn socket_path() -> PathBuf {
+    paths::runtime_dir().join(SOCKET_NAME)
+}
+
+/// Tell the running app, if there is one, that a draft changed in the current profile
+///
+/// Used by headless commands, which write to the database directly. Succeeds
+/// without doing anything when the app isn't running.
+pub fn announce(uuid: &str, kind: ChangeKind) -> Result<(), String> {
+    let mut stream = match std::os::unix::net::UnixStream::connect(socket_path()) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            return Ok(());
+        }
+        Err(e) => return Err(format!("Failed to reach the running app: {}", e)),
+    };
+
+    let mut line = json!({
+        "jsonrpc": "2.0",
+        "method": "drafts.notify",
+        "params": { "uuid": uuid, "kind": kind, "profile": profiles::current() },
+    })
+    .to_string();
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to notify the running app: {}", e))
+}
+
+async fn listen(app: AppHandle) -> Result<(), String> {
+    let path = socket_path();
+
+    // Only the primary instance gets here, so any existing socket is stale
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to bind {:?}: {}", path, e))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict {:?}: {}", path, e))?;
+    tracing::info!("JSON-RPC API v{} listening on {:?}", API_VERSION, path);
+
+    // Fan draft changes out to subscribed connections
+    let (changes, _) = broadcast::channel::<Value>(64);
+    let sender = changes.clone();
+    app.listen(drafts::CHANGED_EVENT, move |event| {
+        if let Ok(change) = serde_json::from_str(event.payload()) {
+            let _ = sender.send(change);
+        }
+    });
+
+    let uid = unsafe { libc::getuid() };
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept connection: {}", e))?;
+
+        match stream.peer_cred() {
+            Ok(cred) if cred.uid() == uid => {}
+            _ => {
+                tracing::warn!("Rejected JSON-RPC connection from another user");
+                continue;
+            }
+        }
+
+        let app = app.clone();
+        let changes = changes.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = handle_connection(app, stream, changes).await {
+                tracing::debug!("JSON-RPC connection closed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    app: AppHandle,
+    stream: UnixStream,
+    changes: broadcast::Sender<Value>,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscription: Option<broadcast::Receiver<Value>> = None;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let (response, subscribe) = handle_line(&app, &line).await;
+                if subscribe && subscription.is_none() {
+                    subscription = Some(changes.subscribe());
+                }
+                if let Some(response) = response {
+                    write_message(&mut writer, &response).await?;
+                }
+            }
+            change = async { subscription.as_mut().unwrap().recv().await }, if subscription.is_some() => {
+                match change {
+                    Ok(params) => {
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "drafts.changed",
+                            "params": params,
+                        });
+                        write_message(&mut writer, &notification).await?;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::debug!("JSON-RPC subscriber missed {} change(s)", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => subscription = None,
+                }
+            }
+        }
+    }
+}
+
+async fn write_message<T: Serialize>(
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+    message: &T,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+}
+
+/// Handle one line of input
+///
+/// Returns the response (none for notifications) and whether the client subscribed.
+async fn handle_line<R: Runtime>(app: &AppHandle<R>, line: &str) -> (Option<Response>, bool) {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(r) => r,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+            return (Some(response(Value::Null, Err(error))), false);
+        }
+    };
+
+    let id = request.id.clone();
+    let subscribe = request.method == "drafts.subscribe";
+    let result = if request.jsonrpc != "2.0" {
+        Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+    } else {
+        call(app, &request.method, request.params).await
+    };
+
+    let subscribed = subscribe && result.is_ok();
+    (id.map(|id| response(id, result)), subscribed)
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Response {
+    let (result, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(e) => (None, Some(e)),
+    };
+    Response {
+        jsonrpc: "2.0",
+        id,
+        result,
+        error,
+    }
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Methods without required params accept both a missing and an empty params object
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::from(e.to_string()))
+}
+
+/// Dispatch a method call
+async fn call<R: Runtime>(app: &AppHandle<R>, method: &str, raw: Value) -> Result<Value, RpcError> {
+    match method {
+        "rpc.version" => Ok(json!({
+            "api_version": API_VERSION,
+            "app_version": app.package_info().version.to_string(),
+        })),
+        "drafts.list" => {
+            let p: ListParams = params(raw)?;
+            let pool = db::pool(app).await?;
+            let filter = ListFilter {
+                archived: p.archived,
+                deleted: p.deleted,
+            };
+            to_value(drafts::list(&pool, filter).await?)
+        }
+        "drafts.get" => {
+            let p: GetParams = params(raw)?;
+            let pool = db::pool(app).await?;
+            to_value(drafts::get(&pool, &p.uuid).await?)
+        }
+        "drafts.create" => {
+            let p: CreateParams = params(raw)?;
+            let pool = db::pool(app).await?;
+            let draft = drafts::create(&pool, &p.content).await?;
+            drafts::notify(app, &draft.uuid, ChangeKind::Created);
+            to_value(draft)
+        }
+        "drafts.append" => {
+            let p: AppendParams = params(raw)?;
+            let pool = db::pool(app).await?;
+            let uuid = match p.uuid {
+                Some(uuid) => uuid,
+                None => {
+                    drafts::pinned(&pool)
+                        .await?
+                        .ok_or_else(|| "No uuid given and no draft is pinned".to_string())?
+                        .uuid
+                }
+            };
+            let draft = drafts::append(&pool, &uuid, &p.text).await?;
+            drafts::notify(app, &draft.uuid, ChangeKind::Updated);
+            to_value(draft)
+        }
+        "drafts.subscribe" => Ok(Value::Bool(true)),
+        "drafts.notify" => {
+            let p: NotifyParams = params(raw)?;
+            let elsewhere = p.profile.is_some_and(|name| name != profiles::current());
+            if elsewhere {
+                return Ok(Value::Bool(false));
+            }
+            drafts::notify(app, &p.uuid, p.kind);
+            Ok(Value::Bool(true))
+        }
+        "capture.open" => {
+            windows::show_capture(app);
+            Ok(Value::Null)
+        }
+        "window.show" => {
+            let p: ShowParams = params(raw)?;
+            match p.draft {
+                Some(uuid) => windows::open_draft(app, &uuid)?,
+                None => windows::show_main(app)?,
+            }
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use tauri::Manager;
+    use tauri::test::MockRuntime;
+    use tauri_plugin_sql::DbInstances;
+
+    /// Handle a line and return the response as JSON, with whether it subscribed
+    async fn send(app: &AppHandle<MockRuntime>, line: &str) -> (Value, bool) {
+        let (response, subscribed) = handle_line(app, line).await;
+        (serde_json::to_value(response).unwrap(), subscribed)
+    }
+
+    fn error_code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().expect("error response")
+    }
+
+    #[test]
+    fn rejects_bad_messages() {
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            let app = app.handle();
+
+            let (response, _) = send(app, "{not json").await;
+            assert_eq!(error_code(&response), PARSE_ERROR);
+            assert_eq!(response["id"], Value::Null);
+
+            let line = r#"{"jsonrpc":"1.0","id":1,"method":"rpc.version"}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(error_code(&response), INVALID_REQUEST);
+            assert_eq!(response["id"], 1);
+
+            let line = r#"{"jsonrpc":"2.0","id":"a","method":"drafts.delete"}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(error_code(&response), METHOD_NOT_FOUND);
+            assert_eq!(response["id"], "a");
+
+            let line = r#"{"jsonrpc":"2.0","id":2,"method":"drafts.get","params":{}}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(error_code(&response), INVALID_PARAMS);
+        });
+    }
+
+    #[test]
+    fn answers_requests_but_not_notifications() {
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            let app = app.handle();
+
+            let line = r#"{"jsonrpc":"2.0","id":7,"method":"rpc.version"}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(response["result"]["api_version"], API_VERSION);
+            assert!(response.get("error").is_none());
+
+            let line = r#"{"jsonrpc":"2.0","method":"rpc.version"}"#;
+            assert!(handle_line(app, line).await.0.is_none());
+
+            let line = r#"{"jsonrpc":"2.0","id":8,"method":"drafts.subscribe"}"#;
+            let (response, subscribed) = send(app, line).await;
+            assert_eq!(response["result"], true);
+            assert!(subscribed);
+        });
+    }
+
+    #[test]
+    fn creates_and_appends_to_drafts() {
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            let (_dir, pool) = testing::pool().await;
+            app.manage(DbInstances::default());
+            db::install(app.handle(), &db::url(), pool.clone())
+                .await
+                .unwrap();
+            let app = app.handle();
+
+            let line = r#"{"jsonrpc":"2.0","id":1,"method":"drafts.append","params":{"text":"x"}}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(error_code(&response), APP_ERROR);
+
+            let line = r#"{"jsonrpc":"2.0","id":2,"method":"drafts.create","params":{"content":"a"}}"#;
+            let (response, _) = send(app, line).await;
+            let uuid = response["result"]["uuid"].as_str().unwrap().to_string();
+
+            let line = json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "drafts.append",
+                "params": { "text": "b", "uuid": uuid },
+            });
+            let (response, _) = send(app, &line.to_string()).await;
+            assert_eq!(response["result"]["content"], "a\n\nb");
+
+            drafts::delete(&pool, &uuid).await.unwrap();
+            let (response, _) = send(app, &line.to_string()).await;
+            assert_eq!(error_code(&response), APP_ERROR);
+
+            let line = r#"{"jsonrpc":"2.0","id":4,"method":"drafts.list"}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(response["result"], json!([]));
+
+            let line = r#"{"jsonrpc":"2.0","id":5,"method":"drafts.list","params":{"deleted":true}}"#;
+            let (response, _) = send(app, line).await;
+            assert_eq!(response["result"][0]["uuid"], uuid.as_str());
+        });
+    }
+
+    #[test]
+    fn announces_changes_made_elsewhere() {
+        tauri::async_runtime::block_on(async {
+            let app = tauri::test::mock_app();
+            let app = app.handle();
+            let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let received = changes.clone();
+            app.listen(drafts::CHANGED_EVENT, move |event| {
+                received.lock().unwrap().push(event.payload().to_string());
+            });
+
+            let notify = |uuid: &str, profile: &str| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "drafts.notify",
+                    "params": { "uuid": uuid, "kind": "created", "profile": profile },
+                })
+                .to_string()
+            };
+
+            let (response, _) = send(app, &notify("a", &profiles::current())).await;
+            assert_eq!(response["result"], true);
+            let (response, _) = send(app, &notify("b", "elsewhere")).await;
+            assert_eq!(response["result"], false);
+
+            let changes = changes.lock().unwrap();
+            assert_eq!(changes.len(), 1);
+            let change: Value = serde_json::from_str(&changes[0]).unwrap();
+            assert_eq!(change["uuid"], "a");
+            assert_eq!(change["kind"], "created");
+        });
+    }
+}
@@ -0,0 +1,192 @@
+//! Graceful shutdown.
+//!
+//! Every exit, whether from the tray, closing the main window, or SIGTERM,
+//! goes through Tauri's `ExitRequested` event. The first request is held
+//! back while the shutdown sequence runs, then the exit is re-issued:
+//!
+//! 1. Ask windows to save unsaved editor content, waiting up to
+//!    [`FLUSH_DEADLINE`] for each of them to confirm
+//! 2. Save window geometry while the windows still exist
+//! 3. Unregister hotkeys and stop text expansion, joining their threads
+//! 4. Close the database, letting in-flight writes finish
+//! 5. Release `update.lock` and finish any `--trace` file
+
+use crate::conf::SettingsState;
+use crate::geometry::GeometryState;
+use crate::{db, trace, updater, windows};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager, RunEvent, Runtime};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+/// Event asking windows to save unsaved editor content now
+pub const FLUSH_EVENT: &str = "app:flush";
+
+/// Event a window emits with its label once its content is saved
+pub const FLUSHED_EVENT: &str = "app:flushed";
+
+/// How long to wait for windows to save before moving on
+pub const FLUSH_DEADLINE: Duration = Duration::from_secs(2);
+
+/// Progress of the shutdown sequence
+#[derive(Default)]
+pub struct ShutdownState {
+    started: AtomicBool,
+    finished: AtomicBool,
+}
+
+/// Run the shutdown sequence before letting the app exit
+pub fn on_run_event(app: &AppHandle, event: &RunEvent) {
+    let RunEvent::ExitRequested { api, code, .. } = event else {
+        return;
+    };
+    let Some(state) = app.try_state::<ShutdownState>() else {
+        return;
+    };
+    if state.finished.load(Ordering::SeqCst) {
+        return;
+    }
+
+    api.prevent_exit();
+    if state.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    let code = code.unwrap_or(0);
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("Shutting down");
+        run(&app).await;
+        app.state::<ShutdownState>()
+            .finished
+            .store(true, Ordering::SeqCst);
+        app.exit(code);
+    });
+}
+
+/// Turn SIGTERM and SIGINT into a normal exit, so they also shut down gracefully
+pub fn handle_signals(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let (mut term, mut int) = match (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) {
+            (Ok(term), Ok(int)) => (term, int),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("Failed to install signal handlers: {}", e);
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = term.recv() => tracing::info!("Received SIGTERM"),
+            _ = int.recv() => tracing::info!("Received SIGINT"),
+        }
+        app.exit(0);
+    });
+}
+
+async fn run(app: &AppHandle) {
+    flush_windows(app).await;
+
+    if let Some(geometry) = app.try_state::<GeometryState>() {
+        geometry.record_all(app);
+    }
+
+    if let Some(settings) = app.try_state::<SettingsState>() {
+        settings.unregister_all().await;
+    }
+
+    if let Err(e) = db::close(app, &db::url()).await {
+        tracing::warn!("{}", e);
+    }
+
+    updater::release_update_lock();
+    trace::finish();
+}
+
+/// Ask windows to save, waiting for each of them until the deadline
+pub async fn flush_windows<R: Runtime>(app: &AppHandle<R>) {
+    let mut waiting: HashSet<&str> = [windows::MAIN, windows::CAPTURE]
+        .into_iter()
+        .filter(|label| app.get_webview_window(label).is_some())
+        .collect();
+    if waiting.is_empty() {
+        return;
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let id = app.listen(FLUSHED_EVENT, move |event| {
+        if let Ok(label) = serde_json::from_str::<String>(event.payload()) {
+            let _ = tx.send(label);
+        }
+    });
+
+    if let Err(e) = app.emit(FLUSH_EVENT, ()) {
+        tracing::warn!("Failed to ask windows to save: {}", e);
+    } else {
+        let confirmed = async {
+            while let Some(label) = rx.recv().await {
+                waiting.remove(label.as_str());
+                if waiting.is_empty() {
+                    break;
+                }
+            }
+        };
+        if tokio::time::timeout(FLUSH_DEADLINE, confirmed)
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "Windows {:?} didn't confirm saving within {:?}",
+                waiting,
+                FLUSH_DEADLINE
+            );
+        }
+    }
+    app.unlisten(id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+    #[test]
+    fn waits_for_every_window_to_flush() {
+        let app = tauri::test::mock_app();
+        for label in [windows::MAIN, windows::CAPTURE] {
+            WebviewWindowBuilder::new(&app, label, WebviewUrl::default())
+                .build()
+                .unwrap();
+        }
+
+        // Confirm from another thread, as windows do, main first
+        let handle = app.handle().clone();
+        app.listen(FLUSH_EVENT, move |_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for label in [windows::MAIN, windows::CAPTURE] {
+                    std::thread::sleep(Duration::from_millis(50));
+                    handle.emit(FLUSHED_EVENT, label).unwrap();
+                }
+            });
+        });
+
+        let started = Instant::now();
+        tauri::async_runtime::block_on(flush_windows(app.handle()));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(started.elapsed() < FLUSH_DEADLINE);
+    }
+
+    #[test]
+    fn skips_waiting_without_windows() {
+        let app = tauri::test::mock_app();
+        let started = Instant::now();
+        tauri::async_runtime::block_on(flush_windows(app.handle()));
+        assert!(started.elapsed() < FLUSH_DEADLINE);
+    }
+}
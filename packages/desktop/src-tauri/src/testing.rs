@@ -0,0 +1,64 @@
+//! Helpers shared by tests: a migrated scratch database and a private
+//! session bus.
+
+use crate::db;
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteConnectOptions;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use tempfile::TempDir;
+
+/// A fully migrated database in a temporary directory, removed with the `TempDir`
+pub async fn pool() -> (TempDir, SqlitePool) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let options = SqliteConnectOptions::new()
+        .filename(dir.path().join("test.db"))
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options)
+        .await
+        .expect("open test database");
+    db::migrate(&pool).await.expect("migrate test database");
+    (dir, pool)
+}
+
+/// A private `dbus-daemon`, stopped when dropped
+pub struct TestBus {
+    daemon: Child,
+    pub address: String,
+}
+
+impl TestBus {
+    /// Start a bus, panicking when `dbus-daemon` can't be run
+    ///
+    /// D-Bus tests fail rather than skip, so a missing daemon in CI can't
+    /// pass unnoticed.
+    pub fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap_or_else(|e| panic!("Failed to run dbus-daemon, which D-Bus tests need: {}", e));
+
+        let mut address = String::new();
+        if let Some(stdout) = daemon.stdout.take() {
+            let _ = BufReader::new(stdout).read_line(&mut address);
+        }
+        let bus = Self {
+            daemon,
+            address: address.trim().to_string(),
+        };
+        assert!(
+            !bus.address.is_empty(),
+            "dbus-daemon didn't print its address"
+        );
+        bus
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
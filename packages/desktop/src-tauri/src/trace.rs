@@ -0,0 +1,71 @@
+//! Chrome trace export for startup and hotkey latency.
+//!
+//! `dashtext --trace` records this crate's `tracing` spans (startup phases,
+//! hotkey registration, and each hotkey from key event to window shown) as
+//! Chrome trace JSON, which Perfetto and `chrome://tracing` can load. Spans
+//! are at debug level, so they stay out of the log at the default levels.
+//! Events keep going to the log plugin while tracing, through `tracing`'s
+//! `log-always` feature.
+
+use crate::paths;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+use time::macros::format_description;
+use tracing::Level;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard, TraceStyle};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
+
+/// Target prefix of this crate's spans
+const CRATE_TARGET: &str = "dashtext_lib";
+
+/// Keeps the trace writer running until the trace is finished
+static GUARD: Mutex<Option<FlushGuard>> = Mutex::new(None);
+
+/// Start writing a trace to `path`, or a timestamped file in the log directory
+///
+/// Returns the path written to.
+pub fn start(path: Option<PathBuf>) -> Result<PathBuf, String> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let stamp = OffsetDateTime::now_utc()
+                .format(format_description!(
+                    "[year][month][day]-[hour][minute][second]"
+                ))
+                .map_err(|e| e.to_string())?;
+            let dir = paths::log_dir()?;
+            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+            dir.join(format!("trace-{}.json", stamp))
+        }
+    };
+
+    // The builder panics on file errors, so open it here
+    let file = File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let (layer, guard) = ChromeLayerBuilder::new()
+        .writer(file)
+        .include_args(true)
+        // Spans run from creation to close, since a hotkey's ends on another thread
+        .trace_style(TraceStyle::Async)
+        .build();
+    let filter = Targets::new().with_target(CRATE_TARGET, Level::DEBUG);
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::registry().with(layer.with_filter(filter)),
+    )
+    .map_err(|e| format!("Failed to start tracing: {}", e))?;
+
+    *GUARD.lock().unwrap() = Some(guard);
+    Ok(path)
+}
+
+/// Finish the trace file, if one is being written
+///
+/// Called at the end of the shutdown sequence, since exiting skips destructors.
+pub fn finish() {
+    if let Some(guard) = GUARD.lock().unwrap().take() {
+        drop(guard);
+        tracing::info!("Trace written");
+    }
+}
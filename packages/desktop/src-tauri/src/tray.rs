@@ -0,0 +1,278 @@
+//! System tray icon and menu.
+//!
+//! The menu lists the pinned draft and the most recently modified drafts.
+//! Draft changes are coalesced for [`REFRESH_DELAY`], and the menu is only
+//! rebuilt when the titles it shows, or the pin, have changed.
+
+use crate::conf::SettingsState;
+use crate::drafts::{self, Draft};
+use crate::{db, windows};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+/// ID of the tray icon
+const TRAY_ID: &str = "main";
+
+/// Event asking the main window to check for updates and show the result
+pub const CHECK_UPDATE_EVENT: &str = "tray:check-update";
+
+/// Number of drafts in the recent submenu
+const RECENT_LIMIT: u32 = 10;
+
+/// Longest draft title shown in the menu, in characters
+const MAX_TITLE_LEN: usize = 40;
+
+/// How long to wait after a draft change for more before checking the menu
+///
+/// Typing saves a draft on every pause, so changes come in bursts.
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+// Menu item IDs; draft items use `DRAFT_PREFIX` followed by the UUID
+const CAPTURE: &str = "capture";
+const SHOW: &str = "show";
+const PAUSE_HOTKEYS: &str = "pause-hotkeys";
+const CHECK_UPDATE: &str = "check-update";
+const QUIT: &str = "quit";
+const DRAFT_PREFIX: &str = "draft:";
+
+/// Drafts as the menu shows them: `(uuid, title)` pairs
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MenuDrafts {
+    pinned: Option<(String, String)>,
+    recent: Vec<(String, String)>,
+}
+
+impl MenuDrafts {
+    fn new(pinned: Option<&Draft>, recent: &[Draft]) -> Self {
+        let entry = |draft: &Draft| (draft.uuid.clone(), menu_title(draft));
+        Self {
+            pinned: pinned.map(entry),
+            recent: recent.iter().map(entry).collect(),
+        }
+    }
+}
+
+/// What the menu currently shows, and whether a refresh is waiting to run
+#[derive(Default)]
+struct TrayState {
+    shown: Mutex<MenuDrafts>,
+    scheduled: AtomicBool,
+}
+
+/// Create the tray icon and keep its menu in sync with drafts
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    app.manage(TrayState::default());
+    let menu = build_menu(app, None, &[])?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("DashText")
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    refresh(app.clone());
+    let handle = app.clone();
+    app.listen(drafts::CHANGED_EVENT, move |_| schedule(handle.clone()));
+    Ok(())
+}
+
+/// Rebuild the menu from the database in the background
+pub fn refresh(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = rebuild(&app).await {
+            tracing::warn!("Failed to refresh tray menu: {}", e);
+        }
+    });
+}
+
+/// Refresh the menu once changes settle, unless a refresh is already waiting
+fn schedule(app: AppHandle) {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return;
+    };
+    if state.scheduled.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(REFRESH_DELAY).await;
+        // Cleared before querying, so changes made during the query get their own refresh
+        app.state::<TrayState>()
+            .scheduled
+            .store(false, Ordering::SeqCst);
+        if let Err(e) = rebuild(&app).await {
+            tracing::warn!("Failed to refresh tray menu: {}", e);
+        }
+    });
+}
+
+async fn rebuild(app: &AppHandle) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let pinned = drafts::pinned(&pool).await?;
+    let recent = drafts::recent(&pool, RECENT_LIMIT).await?;
+
+    let shown = MenuDrafts::new(pinned.as_ref(), &recent);
+    if let Some(state) = app.try_state::<TrayState>() {
+        let mut current = state.shown.lock().unwrap();
+        if *current == shown {
+            return Ok(());
+        }
+        *current = shown;
+    }
+
+    let menu = build_menu(app, pinned.as_ref(), &recent)
+        .map_err(|e| format!("Failed to build tray menu: {}", e))?;
+    let tray = app
+        .tray_by_id(TRAY_ID)
+        .ok_or_else(|| "Tray icon not found".to_string())?;
+    tray.set_menu(Some(menu))
+        .map_err(|e| format!("Failed to set tray menu: {}", e))
+}
+
+fn build_menu(
+    app: &AppHandle,
+    pinned: Option<&Draft>,
+    recent: &[Draft],
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let pinned_item = match pinned {
+        Some(draft) => MenuItem::with_id(
+            app,
+            draft_id(draft),
+            format!("Pinned: {}", menu_title(draft)),
+            true,
+            None::<&str>,
+        )?,
+        None => MenuItem::new(app, "No Pinned Draft", false, None::<&str>)?,
+    };
+
+    let recent_items = recent
+        .iter()
+        .map(|draft| MenuItem::with_id(app, draft_id(draft), menu_title(draft), true, None::<&str>))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_menu = if recent_items.is_empty() {
+        let empty = MenuItem::new(app, "No Drafts", false, None::<&str>)?;
+        Submenu::with_items(app, "Recent Drafts", true, &[&empty])?
+    } else {
+        let items: Vec<&dyn IsMenuItem<tauri::Wry>> = recent_items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+            .collect();
+        Submenu::with_items(app, "Recent Drafts", true, &items)?
+    };
+
+    let paused = app
+        .try_state::<SettingsState>()
+        .is_some_and(|state| state.hotkeys_paused());
+
+    Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, CAPTURE, "Quick Capture", true, None::<&str>)?,
+            &MenuItem::with_id(app, SHOW, "Open DashText", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &pinned_item,
+            &recent_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &CheckMenuItem::with_id(
+                app,
+                PAUSE_HOTKEYS,
+                "Pause Hotkeys",
+                true,
+                paused,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(app, CHECK_UPDATE, "Check for Updates…", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, QUIT, "Quit DashText", true, None::<&str>)?,
+        ],
+    )
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let result = match id {
+        CAPTURE => {
+            windows::show_capture(app);
+            Ok(())
+        }
+        SHOW => windows::show_main(app),
+        PAUSE_HOTKEYS => {
+            if let Some(state) = app.try_state::<SettingsState>() {
+                state.set_hotkeys_paused(!state.hotkeys_paused());
+            }
+            Ok(())
+        }
+        CHECK_UPDATE => windows::show_main(app).and_then(|_| {
+            app.emit_to(windows::MAIN, CHECK_UPDATE_EVENT, ())
+                .map_err(|e| format!("Failed to request update check: {}", e))
+        }),
+        QUIT => {
+            app.exit(0);
+            Ok(())
+        }
+        _ => match id.strip_prefix(DRAFT_PREFIX) {
+            Some(uuid) => windows::open_draft(app, uuid),
+            None => Ok(()),
+        },
+    };
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to handle tray menu item {}: {}", id, e);
+    }
+}
+
+fn draft_id(draft: &Draft) -> String {
+    format!("{}{}", DRAFT_PREFIX, draft.uuid)
+}
+
+/// Draft title shortened for the menu, with `&` kept literal
+fn menu_title(draft: &Draft) -> String {
+    let title = draft.title();
+    let title = match title.char_indices().nth(MAX_TITLE_LEN) {
+        Some((end, _)) => format!("{}…", title[..end].trim_end()),
+        None => title.to_string(),
+    };
+    title.replace('&', "&&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn tracks_only_what_the_menu_shows() {
+        tauri::async_runtime::block_on(async {
+            let (_dir, pool) = testing::pool().await;
+            let draft = drafts::create(&pool, "Shopping\nmilk").await.unwrap();
+            let snapshot = || async {
+                let pinned = drafts::pinned(&pool).await.unwrap();
+                let recent = drafts::recent(&pool, RECENT_LIMIT).await.unwrap();
+                MenuDrafts::new(pinned.as_ref(), &recent)
+            };
+            let shown = snapshot().await;
+            assert_eq!(shown.recent, [(draft.uuid.clone(), "Shopping".to_string())]);
+
+            // Editing below the title leaves the menu as it is
+            drafts::save(&pool, &draft.uuid, "Shopping\nmilk\neggs")
+                .await
+                .unwrap();
+            assert_eq!(snapshot().await, shown);
+
+            drafts::save(&pool, &draft.uuid, "Groceries\nmilk\neggs")
+                .await
+                .unwrap();
+            let renamed = snapshot().await;
+            assert_ne!(renamed, shown);
+
+            drafts::pin(&pool, &draft.uuid).await.unwrap();
+            assert_ne!(snapshot().await, renamed);
+        });
+    }
+}
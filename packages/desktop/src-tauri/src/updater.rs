@@ -1,3 +1,5 @@
+use crate::notify::{self, Action, Category, Notification, NotificationState};
+use crate::paths;
 use fs2::FileExt;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
@@ -6,8 +8,8 @@ use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
-use tauri::{AppHandle, Emitter};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
 
 const UPDATE_MANIFEST_URL: &str =
     "https://github.com/joshuadavidthomas/dashtext/releases/latest/download/latest.json";
@@ -52,7 +54,7 @@ pub struct DownloadProgress {
 }
 
 /// Get the current platform key for latest.json
-fn get_platform_key() -> &'static str {
+pub(crate) fn get_platform_key() -> &'static str {
     #[cfg(target_arch = "x86_64")]
     {
         "linux-x86_64"
@@ -96,9 +98,7 @@ fn can_write_to_binary_location() -> bool {
 
 /// Get the lock file path for update operations
 fn get_lock_file_path() -> Result<PathBuf, String> {
-    let data_dir = dirs::data_local_dir()
-        .ok_or_else(|| "Could not determine local data directory".to_string())?;
-    let app_dir = data_dir.join("dashtext");
+    let app_dir = paths::app_data_dir()?;
     fs::create_dir_all(&app_dir)
         .map_err(|e| format!("Failed to create app data directory: {}", e))?;
     Ok(app_dir.join("update.lock"))
@@ -115,6 +115,24 @@ fn acquire_update_lock() -> Result<File, String> {
     Ok(lock_file)
 }
 
+/// Remove the update lock file at shutdown, unless an update is still holding it
+pub fn release_update_lock() {
+    let Ok(lock_path) = get_lock_file_path() else {
+        return;
+    };
+    let Ok(lock_file) = File::open(&lock_path) else {
+        return;
+    };
+
+    if lock_file.try_lock_exclusive().is_ok() {
+        let _ = fs::remove_file(&lock_path);
+        let _ = FileExt::unlock(&lock_file);
+        tracing::info!("Released update lock");
+    } else {
+        tracing::warn!("Update still in progress; its lock is released on exit");
+    }
+}
+
 /// Fetch the update manifest from the server
 async fn fetch_manifest() -> Result<UpdateManifest, String> {
     let response = reqwest::get(UPDATE_MANIFEST_URL)
@@ -254,6 +272,25 @@ fn verify_checksum(file_path: &PathBuf, expected_sha256: &str) -> Result<(), Str
     Ok(())
 }
 
+/// Mark a binary executable
+///
+/// A portable copy may live on a filesystem without Unix modes, like FAT on a
+/// USB stick, where every file is already executable and chmod fails.
+fn set_executable(path: &Path) -> Result<(), String> {
+    let mut perms = fs::metadata(path)
+        .map_err(|e| format!("Failed to get binary metadata: {}", e))?
+        .permissions();
+    perms.set_mode(0o755);
+    match fs::set_permissions(path, perms) {
+        Ok(()) => Ok(()),
+        Err(e) if paths::is_portable() => {
+            tracing::debug!("Ignoring chmod failure on portable binary {:?}: {}", path, e);
+            Ok(())
+        }
+        Err(e) => Err(format!("Failed to set binary permissions: {}", e)),
+    }
+}
+
 /// Extract a tar.gz archive and return the path to the binary
 fn extract_tarball(tarball_path: &PathBuf, dest_dir: &PathBuf) -> Result<PathBuf, String> {
     let file =
@@ -306,6 +343,19 @@ pub async fn check_update(app: AppHandle) -> Result<Option<UpdateInfo>, String>
         .get(platform_key)
         .ok_or_else(|| format!("No update available for platform: {}", platform_key))?;
 
+    let announce = app
+        .try_state::<NotificationState>()
+        .is_some_and(|state| state.first_announcement(&manifest.version));
+    if announce {
+        let notification = Notification::new(
+            Category::Update,
+            "Update available",
+            format!("DashText {} is available", manifest.version),
+        )
+        .action(Action::InstallUpdate);
+        notify::send(&app, notification);
+    }
+
     Ok(Some(UpdateInfo {
         current_version,
         new_version: manifest.version,
@@ -376,12 +426,7 @@ pub async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
     let new_binary = extract_tarball(&tarball_path, &extract_dir)?;
 
     // Make the new binary executable
-    let mut perms = fs::metadata(&new_binary)
-        .map_err(|e| format!("Failed to get binary metadata: {}", e))?
-        .permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&new_binary, perms)
-        .map_err(|e| format!("Failed to set binary permissions: {}", e))?;
+    set_executable(&new_binary)?;
 
     // Prepare paths for atomic swap
     let backup_path = current_exe.with_extension("old");
@@ -392,12 +437,7 @@ pub async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
         .map_err(|e| format!("Failed to copy new binary: {}", e))?;
 
     // Make the copied binary executable
-    let mut perms = fs::metadata(&temp_new_path)
-        .map_err(|e| format!("Failed to get temp binary metadata: {}", e))?
-        .permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&temp_new_path, perms)
-        .map_err(|e| format!("Failed to set temp binary permissions: {}", e))?;
+    set_executable(&temp_new_path)?;
 
     // Remove old backup if it exists
     if backup_path.exists() {
@@ -440,7 +480,7 @@ pub fn restart_app() -> Result<(), String> {
         use std::os::unix::process::CommandExt;
         let err = std::process::Command::new(&current_exe).exec();
         // exec() only returns if there's an error
-        return Err(format!("Failed to restart: {}", err));
+        Err(format!("Failed to restart: {}", err))
     }
 
     #[cfg(not(unix))]
@@ -0,0 +1,171 @@
+//! Showing and focusing application windows from Rust.
+
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{
+    AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Window,
+    WindowEvent,
+};
+
+/// Label of the main editor window
+pub const MAIN: &str = "main";
+
+/// Label of the quick-capture window
+pub const CAPTURE: &str = "capture";
+
+/// Event asking the capture window to clear its editor before it's shown
+pub const RESET_CAPTURE_EVENT: &str = "capture:reset";
+
+/// Event asking the main window to navigate to a draft
+pub const OPEN_DRAFT_EVENT: &str = "window:open-draft";
+
+/// When the capture window was triggered, and its span, until the window has focus
+///
+/// `show` only queues the window for the event loop, so the latency and the
+/// `--trace` span end at the first focus event after it.
+#[derive(Default)]
+pub struct PendingShow(Mutex<Option<(Instant, tracing::Span)>>);
+
+/// Show, unminimize and focus the main window
+pub fn show_main<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let window = app
+        .get_webview_window(MAIN)
+        .ok_or_else(|| "Main window not found".to_string())?;
+
+    window
+        .unminimize()
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show main window: {}", e))
+}
+
+/// Create the capture window hidden, so showing it later doesn't wait for a webview to load
+pub fn create_capture<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
+    let _span = tracing::debug_span!("create_capture").entered();
+    WebviewWindowBuilder::new(app, CAPTURE, WebviewUrl::App("/capture".into()))
+        .title("Quick Capture")
+        .inner_size(600.0, 300.0)
+        .decorations(false)
+        .always_on_top(true)
+        .center()
+        .focused(false)
+        .visible(false)
+        .background_color(tauri::window::Color(0x22, 0x24, 0x36, 0xff))
+        .resizable(true)
+        .skip_taskbar(true)
+        .build()
+        .map_err(|e| format!("Failed to create capture window: {}", e))
+}
+
+/// Open the quick-capture window
+pub fn show_capture<R: Runtime>(app: &AppHandle<R>) {
+    show_capture_since(app, Instant::now());
+}
+
+/// Open the quick-capture window, logging its latency from `triggered`
+///
+/// Hotkeys pass the time of the key event, so the latency includes the
+/// backend's own delay in delivering it.
+pub fn show_capture_since<R: Runtime>(app: &AppHandle<R>, triggered: Instant) {
+    let span = tracing::debug_span!("show_capture").entered();
+
+    let result = match app.get_webview_window(CAPTURE) {
+        Some(window) => Ok(window),
+        None => {
+            tracing::warn!("Capture window wasn't pre-warmed, creating it now");
+            create_capture(app)
+        }
+    }
+    .and_then(|window| {
+        if let Some(geometry) = app.try_state::<crate::geometry::GeometryState>() {
+            tracing::debug_span!("place_capture").in_scope(|| geometry.place_capture(&window));
+        }
+        // Clear the previous capture before the window is visible
+        let _ = app.emit_to(CAPTURE, RESET_CAPTURE_EVENT, ());
+        window
+            .show()
+            .and_then(|_| window.set_focus())
+            .map_err(|e| format!("Failed to show capture window: {}", e))
+    });
+
+    match result {
+        Ok(()) => {
+            tracing::debug!("Capture window show queued in {:?}", triggered.elapsed());
+            // Holding the span also holds the hotkey span it's nested in
+            if let Some(pending) = app.try_state::<PendingShow>() {
+                *pending.0.lock().unwrap() = Some((triggered, span.exit()));
+            }
+        }
+        Err(e) => tracing::warn!("{}", e),
+    }
+}
+
+/// Keep the capture window alive between captures, and quit once the main window is gone
+pub fn on_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) {
+    match (window.label(), event) {
+        (CAPTURE, WindowEvent::Focused(true)) => {
+            let pending = window
+                .try_state::<PendingShow>()
+                .and_then(|pending| pending.0.lock().unwrap().take());
+            if let Some((triggered, span)) = pending {
+                span.in_scope(|| {
+                    tracing::info!("Capture window shown in {:?}", triggered.elapsed())
+                });
+            }
+        }
+        (CAPTURE, WindowEvent::CloseRequested { api, .. }) => {
+            api.prevent_close();
+            if let Some(pending) = window.try_state::<PendingShow>() {
+                pending.0.lock().unwrap().take();
+            }
+            if let Err(e) = window.hide() {
+                tracing::warn!("Failed to hide capture window: {}", e);
+            }
+        }
+        // The hidden capture window would otherwise keep the app running
+        (MAIN, WindowEvent::Destroyed) => window.app_handle().exit(0),
+        _ => {}
+    }
+}
+
+/// Show the main window and navigate it to a draft
+pub fn open_draft<R: Runtime>(app: &AppHandle<R>, uuid: &str) -> Result<(), String> {
+    show_main(app)?;
+    app.emit_to(MAIN, OPEN_DRAFT_EVENT, uuid)
+        .map_err(|e| format!("Failed to open draft {}: {}", uuid, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn pending(app: &AppHandle<tauri::test::MockRuntime>) -> Option<Instant> {
+        let state = app.state::<PendingShow>();
+        let pending = state.0.lock().unwrap();
+        pending.as_ref().map(|(triggered, _)| *triggered)
+    }
+
+    #[test]
+    fn capture_is_shown_once_focused() {
+        let app = tauri::test::mock_app();
+        app.manage(PendingShow::default());
+        let window = create_capture(app.handle()).unwrap();
+
+        let pressed = Instant::now() - Duration::from_millis(20);
+        show_capture_since(app.handle(), pressed);
+        assert_eq!(pending(app.handle()), Some(pressed));
+
+        // Other windows' focus doesn't end it
+        let main = WebviewWindowBuilder::new(&app, MAIN, WebviewUrl::default())
+            .build()
+            .unwrap();
+        on_window_event(&main.as_ref().window(), &WindowEvent::Focused(true));
+        assert_eq!(pending(app.handle()), Some(pressed));
+
+        on_window_event(&window.as_ref().window(), &WindowEvent::Focused(false));
+        assert_eq!(pending(app.handle()), Some(pressed));
+        on_window_event(&window.as_ref().window(), &WindowEvent::Focused(true));
+        assert_eq!(pending(app.handle()), None);
+    }
+}
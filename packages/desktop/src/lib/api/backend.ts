@@ -1,135 +1,89 @@
-import { eq, desc, isNull } from 'drizzle-orm';
-import { getDb, drafts, type Draft, type NewDraft } from '$lib/db';
+import { invoke } from '@tauri-apps/api/core';
 import type { DraftAPI, DraftData } from '@dashtext/lib';
-import { generateUUID } from '@dashtext/lib';
 
 /**
- * Convert Drizzle row (camelCase) to public DraftData (snake_case, UUID only)
+ * Draft as serialized by the Rust `drafts` module
  */
-function toApiFormat(row: Draft): DraftData {
+interface NativeDraft {
+  uuid: string;
+  content: string;
+  created_at: string;
+  modified_at: string;
+  deleted_at: string | null;
+  archived: boolean;
+  pinned: boolean;
+}
+
+/**
+ * Result of `pin_draft`, with the draft that was pinned before
+ */
+interface PinChange {
+  draft: NativeDraft;
+  previous: string | null;
+}
+
+/**
+ * Convert a native draft to public DraftData, omitting unset state fields
+ */
+function toApiFormat(row: NativeDraft): DraftData {
   return {
     uuid: row.uuid,
     content: row.content,
-    created_at: row.createdAt,
-    modified_at: row.modifiedAt,
-    ...(row.deletedAt && { deleted_at: row.deletedAt }),
+    created_at: row.created_at,
+    modified_at: row.modified_at,
+    ...(row.deleted_at && { deleted_at: row.deleted_at }),
     ...(row.archived && { archived: row.archived }),
     ...(row.pinned && { pinned: row.pinned }),
   };
 }
 
-/**
- * Get current ISO timestamp string
- */
-function getNow(): string {
-  return new Date().toISOString();
-}
-
 const tauriBackend: DraftAPI = {
   async list(): Promise<DraftData[]> {
-    const db = await getDb();
-    const rows = await db.select()
-      .from(drafts)
-      .where(isNull(drafts.deletedAt))
-      .orderBy(desc(drafts.modifiedAt));
+    const rows = await invoke<NativeDraft[]>('list_drafts');
     return rows.map(toApiFormat);
   },
 
   async create(): Promise<DraftData> {
-    const db = await getDb();
-    const now = getNow();
-    const result = await db.insert(drafts).values({
-      uuid: generateUUID(),
-      content: '',
-      createdAt: now,
-      modifiedAt: now,
-      archived: false,
-      pinned: false,
-    }).returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('create_draft'));
   },
 
   async get(uuid: string): Promise<DraftData | null> {
-    const db = await getDb();
-    const rows = await db.select()
-      .from(drafts)
-      .where(eq(drafts.uuid, uuid));
-    return rows.length > 0 ? toApiFormat(rows[0]) : null;
+    const row = await invoke<NativeDraft | null>('get_draft', { uuid });
+    return row ? toApiFormat(row) : null;
   },
 
   async save(uuid: string, content: string): Promise<DraftData> {
-    const db = await getDb();
-    const now = getNow();
-    const result = await db.update(drafts)
-      .set({ content, modifiedAt: now })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('save_draft', { uuid, content }));
   },
 
   async archive(uuid: string): Promise<DraftData> {
-    const db = await getDb();
-    const result = await db.update(drafts)
-      .set({ archived: true })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('archive_draft', { uuid }));
   },
 
   async unarchive(uuid: string): Promise<DraftData> {
-    const db = await getDb();
-    const result = await db.update(drafts)
-      .set({ archived: false })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('unarchive_draft', { uuid }));
   },
 
   async pin(uuid: string): Promise<DraftData> {
-    const db = await getDb();
-    
-    // Unpin all other drafts first (single pin constraint)
-    await db.update(drafts)
-      .set({ pinned: false })
-      .where(eq(drafts.pinned, true));
-    
-    // Pin the requested draft
-    const result = await db.update(drafts)
-      .set({ pinned: true })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    // Unpinning any other draft happens in the same transaction
+    const change = await invoke<PinChange>('pin_draft', { uuid });
+    return toApiFormat(change.draft);
   },
 
   async unpin(uuid: string): Promise<DraftData> {
-    const db = await getDb();
-    const result = await db.update(drafts)
-      .set({ pinned: false })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('unpin_draft', { uuid }));
   },
 
   async restore(uuid: string): Promise<DraftData> {
-    const db = await getDb();
-    const result = await db.update(drafts)
-      .set({ deletedAt: null })
-      .where(eq(drafts.uuid, uuid))
-      .returning();
-    return toApiFormat(result[0]);
+    return toApiFormat(await invoke<NativeDraft>('restore_draft', { uuid }));
   },
 
   async delete(uuid: string): Promise<void> {
-    const db = await getDb();
-    const now = getNow();
-    await db.update(drafts)
-      .set({ deletedAt: now })
-      .where(eq(drafts.uuid, uuid));
+    await invoke('delete_draft', { uuid });
   },
 
   async hardDelete(uuid: string): Promise<void> {
-    const db = await getDb();
-    await db.delete(drafts).where(eq(drafts.uuid, uuid));
+    await invoke('hard_delete_draft', { uuid });
   },
 };
 
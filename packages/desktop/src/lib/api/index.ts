@@ -2,3 +2,6 @@ import { DraftClient } from '@dashtext/lib/api';
 import backend from './backend';
 
 export const drafts = new DraftClient(backend);
+export { searchDrafts, type SearchFilter, type SearchHit, type SearchMatch } from './search';
+export { listRevisions, getRevision, diffRevision, restoreRevision, type RevisionInfo, type Revision, type DiffLine } from './revisions';
+export { listTrash, emptyTrash, getTrashSettings, setTrashRetention, type TrashSettings, type PurgeReport } from './trash';
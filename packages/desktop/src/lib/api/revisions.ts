@@ -0,0 +1,47 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface RevisionInfo {
+  id: number;
+  created_at: string;
+  /** Last save folded into this revision */
+  updated_at: string;
+  /** Content length in bytes */
+  size: number;
+}
+
+export interface Revision extends RevisionInfo {
+  content: string;
+}
+
+/**
+ * One line of a diff; `removed` lines are only in the revision, `added` only in the draft
+ */
+export interface DiffLine {
+  tag: 'equal' | 'removed' | 'added';
+  text: string;
+}
+
+/**
+ * A draft's revisions, newest first
+ */
+export async function listRevisions(uuid: string): Promise<RevisionInfo[]> {
+  return invoke<RevisionInfo[]>('list_revisions', { uuid });
+}
+
+export async function getRevision(uuid: string, id: number): Promise<Revision | null> {
+  return invoke<Revision | null>('get_revision', { uuid, id });
+}
+
+/**
+ * Line diff from a revision to the draft's current content
+ */
+export async function diffRevision(uuid: string, id: number): Promise<DiffLine[]> {
+  return invoke<DiffLine[]>('diff_revision', { uuid, id });
+}
+
+/**
+ * Restore a draft to a revision; the replaced content stays in the history
+ */
+export async function restoreRevision(uuid: string, id: number): Promise<void> {
+  await invoke('restore_revision', { uuid, id });
+}
@@ -0,0 +1,48 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface SearchFilter {
+  /** Include archived drafts */
+  archived?: boolean;
+  /** Include drafts in the trash */
+  deleted?: boolean;
+  /** Only the pinned draft */
+  pinned?: boolean;
+}
+
+/**
+ * A matched range; offsets are string indices, line is 1-based
+ */
+export interface SearchMatch {
+  line: number;
+  start: number;
+  end: number;
+}
+
+export interface SearchHit {
+  draft: {
+    uuid: string;
+    content: string;
+    created_at: string;
+    modified_at: string;
+    deleted_at: string | null;
+    archived: boolean;
+    pinned: boolean;
+  };
+  /** bm25 score; lower is a better match */
+  rank: number;
+  snippet: { text: string; highlights: SearchMatch[] };
+  /** Every match within the draft's content */
+  matches: SearchMatch[];
+}
+
+/**
+ * Full-text search over drafts, best matches first.
+ * Supports `"quoted phrases"` and `prefix*` words.
+ */
+export async function searchDrafts(
+  query: string,
+  filter?: SearchFilter,
+  limit?: number,
+): Promise<SearchHit[]> {
+  return invoke<SearchHit[]>('search_drafts', { query, filter, limit });
+}
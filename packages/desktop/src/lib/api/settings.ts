@@ -6,12 +6,34 @@ const DEFAULT_CAPTURE_SHORTCUT = 'CommandOrControl+Shift+C';
 
 export interface AppSettings {
   captureShortcut: string;
+  /** Null when silent capture is off */
+  silentCaptureShortcut: string | null;
+  /** Draft silent captures append to; null means the pinned draft */
+  inboxDraftUuid: string | null;
+  textExpansionEnabled: boolean;
+  /** Window classes where snippets aren't expanded */
+  textExpansionExclusions: string[];
+}
+
+/**
+ * A draft that silent captures can be appended to
+ */
+export interface InboxCandidate {
+  uuid: string;
+  title: string;
 }
 
 function getNow(): string {
   return new Date().toISOString();
 }
 
+function parseExclusions(value: string | null): string[] {
+  return (value ?? '')
+    .split(',')
+    .map((e) => e.trim())
+    .filter(Boolean);
+}
+
 /**
  * Load settings from database
  */
@@ -27,6 +49,10 @@ export async function loadSettings(): Promise<AppSettings> {
   if (rows.length > 0) {
     return {
       captureShortcut: rows[0].captureShortcut ?? DEFAULT_CAPTURE_SHORTCUT,
+      silentCaptureShortcut: rows[0].silentCaptureShortcut ?? null,
+      inboxDraftUuid: rows[0].inboxDraftUuid ?? null,
+      textExpansionEnabled: rows[0].textExpansionEnabled,
+      textExpansionExclusions: parseExclusions(rows[0].textExpansionExclusions),
     };
   }
 
@@ -41,6 +67,10 @@ export async function loadSettings(): Promise<AppSettings> {
 
   return {
     captureShortcut: DEFAULT_CAPTURE_SHORTCUT,
+    silentCaptureShortcut: null,
+    inboxDraftUuid: null,
+    textExpansionEnabled: false,
+    textExpansionExclusions: [],
   };
 }
 
@@ -79,3 +109,123 @@ export async function initializeCaptureShortcut(): Promise<void> {
   const { captureShortcut } = await loadSettings();
   await invoke('register_capture_shortcut', { shortcut: captureShortcut });
 }
+
+/**
+ * Make the app the default handler for dashtext:// links
+ *
+ * Returns the path of the desktop entry that was written.
+ */
+export async function registerUrlHandler(): Promise<string> {
+  return invoke<string>('register_url_handler');
+}
+
+/**
+ * Save silent capture settings and register them with the backend
+ *
+ * A null shortcut turns silent capture off.
+ */
+export async function saveSilentCapture(
+  shortcut: string | null,
+  inboxDraftUuid: string | null
+): Promise<void> {
+  const db = await getDb();
+  const now = getNow();
+
+  await db
+    .insert(settings)
+    .values({
+      id: 1,
+      silentCaptureShortcut: shortcut,
+      inboxDraftUuid,
+      createdAt: now,
+      updatedAt: now,
+    })
+    .onConflictDoUpdate({
+      target: settings.id,
+      set: {
+        silentCaptureShortcut: shortcut,
+        inboxDraftUuid,
+        updatedAt: now,
+      },
+    });
+
+  await registerSilentCapture(shortcut, inboxDraftUuid);
+}
+
+/**
+ * Register silent capture from the database on app startup
+ */
+export async function initializeSilentCapture(): Promise<void> {
+  const { silentCaptureShortcut, inboxDraftUuid } = await loadSettings();
+  if (silentCaptureShortcut) {
+    await registerSilentCapture(silentCaptureShortcut, inboxDraftUuid);
+  }
+}
+
+async function registerSilentCapture(
+  shortcut: string | null,
+  inboxDraftUuid: string | null
+): Promise<void> {
+  if (shortcut) {
+    await invoke('register_silent_capture_shortcut', { shortcut, inboxUuid: inboxDraftUuid });
+  } else {
+    await invoke('unregister_silent_capture_shortcut');
+  }
+}
+
+/**
+ * Save text expansion settings and start or stop the expander
+ *
+ * The setting is only saved once the expander has started, so a missing
+ * input group permission doesn't leave it enabled.
+ */
+export async function saveTextExpansion(enabled: boolean, exclusions: string[]): Promise<void> {
+  if (enabled) {
+    await invoke('enable_text_expansion', { exclusions });
+  } else {
+    await invoke('disable_text_expansion');
+  }
+
+  const db = await getDb();
+  const now = getNow();
+  const textExpansionExclusions = exclusions.join(',') || null;
+
+  await db
+    .insert(settings)
+    .values({
+      id: 1,
+      textExpansionEnabled: enabled,
+      textExpansionExclusions,
+      createdAt: now,
+      updatedAt: now,
+    })
+    .onConflictDoUpdate({
+      target: settings.id,
+      set: {
+        textExpansionEnabled: enabled,
+        textExpansionExclusions,
+        updatedAt: now,
+      },
+    });
+}
+
+/**
+ * Start text expansion on app startup if it's enabled
+ */
+export async function initializeTextExpansion(): Promise<void> {
+  const { textExpansionEnabled, textExpansionExclusions } = await loadSettings();
+  if (textExpansionEnabled) {
+    await invoke('enable_text_expansion', { exclusions: textExpansionExclusions });
+  }
+}
+
+/**
+ * Drafts that can be picked as the silent capture inbox, titled by their first line
+ */
+export async function listInboxCandidates(): Promise<InboxCandidate[]> {
+  const rows = await invoke<{ uuid: string; content: string }[]>('list_drafts');
+  return rows.map((row) => ({
+    uuid: row.uuid,
+    title: row.content.split('\n', 1)[0].trim() || 'Untitled',
+  }));
+}
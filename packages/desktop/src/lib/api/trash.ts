@@ -0,0 +1,43 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { DraftData } from '@dashtext/lib';
+
+export interface TrashSettings {
+  /** Days a draft stays in the trash before it's purged; 0 keeps it until emptied */
+  retention_days: number;
+}
+
+/**
+ * What a purge removed
+ */
+export interface PurgeReport {
+  /** UUIDs of the purged drafts */
+  drafts: string[];
+  revisions: number;
+  chunks: number;
+  /** Size of the deleted content, revisions and Automerge chunks */
+  deleted_bytes: number;
+  /** How much smaller the database file got once the freed pages were vacuumed */
+  reclaimed_bytes: number;
+}
+
+/**
+ * Drafts in the trash, most recently deleted first
+ */
+export async function listTrash(): Promise<DraftData[]> {
+  return invoke<DraftData[]>('list_trash');
+}
+
+/**
+ * Delete everything in the trash for good
+ */
+export async function emptyTrash(): Promise<PurgeReport> {
+  return invoke<PurgeReport>('empty_trash');
+}
+
+export async function getTrashSettings(): Promise<TrashSettings> {
+  return invoke<TrashSettings>('get_trash_settings');
+}
+
+export async function setTrashRetention(days: number): Promise<void> {
+  await invoke('set_trash_retention', { days });
+}
@@ -1,26 +1,8 @@
-import { WebviewWindow } from '@tauri-apps/api/webviewWindow';
+import { invoke } from '@tauri-apps/api/core';
 
 export { default as CaptureEditor } from './CaptureEditor.svelte';
 
+/** Show the capture window, which the backend keeps pre-warmed */
 export async function openQuickCapture() {
-  const existing = await WebviewWindow.getByLabel('capture');
-  if (existing) {
-    await existing.setFocus();
-    return existing;
-  }
-
-  return new WebviewWindow('capture', {
-    url: '/capture',
-    title: 'Quick Capture',
-    width: 600,
-    height: 300,
-    decorations: false,
-    alwaysOnTop: true,
-    center: true,
-    focus: false,
-    visible: false,
-    backgroundColor: '#222436',
-    resizable: true,
-    skipTaskbar: true,
-  });
+  await invoke('show_capture');
 }
@@ -0,0 +1 @@
+export { default as CrashReportDialog } from './CrashReportDialog.svelte';
@@ -1,3 +1,4 @@
+import { invoke } from '@tauri-apps/api/core';
 import Database from '@tauri-apps/plugin-sql';
 import { drizzle } from 'drizzle-orm/sqlite-proxy';
 import { drafts, type Draft, type NewDraft, settings, type Settings, type NewSettings } from '@dashtext/lib/db';
@@ -6,8 +7,6 @@ import { getSyncEngine, initializeSyncEngine, migrateExistingDrafts } from '@das
 
 export { drafts, type Draft, type NewDraft, settings, type Settings, type NewSettings };
 
-const DB_URL = 'sqlite:dashtext.db';
-
 let db: ReturnType<typeof drizzle> | null = null;
 let sqliteInstance: Database | null = null;
 
@@ -25,7 +24,9 @@ function isSelectQuery(sql: string): boolean {
 export async function getDb() {
   if (db) return db;
 
-  const sqlite = await Database.load(DB_URL);
+  // Each profile has its own database file
+  const url = await invoke<string>('get_database_url');
+  const sqlite = await Database.load(url);
   sqliteInstance = sqlite;
 
   db = drizzle(
@@ -0,0 +1,23 @@
+import { emit, listen } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
+
+type FlushHandler = () => Promise<void>;
+
+const handlers = new Set<FlushHandler>();
+
+/** Run `handler` when the app asks windows to save before shutting down */
+export function onFlush(handler: FlushHandler): () => void {
+	handlers.add(handler);
+	return () => handlers.delete(handler);
+}
+
+/** Answer `app:flush` with this window's label once every handler has run */
+export function listenForFlush() {
+	return listen('app:flush', async () => {
+		try {
+			await Promise.allSettled([...handlers].map(handler => handler()));
+		} finally {
+			await emit('app:flushed', getCurrentWebviewWindow().label);
+		}
+	});
+}
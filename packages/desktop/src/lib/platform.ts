@@ -1,4 +1,5 @@
 import { getCurrentWindow } from '@tauri-apps/api/window';
+import { listen } from '@tauri-apps/api/event';
 import { replaceState, goto } from '$app/navigation';
 import { drafts } from '$lib/api';
 import type { PlatformCapabilities } from '@dashtext/lib/platform';
@@ -20,6 +21,17 @@ export const desktopPlatform: PlatformCapabilities = {
     return () => unlisten?.();
   },
 
+  onDraftsChanged(callback) {
+    // Changes this window's own commands made are already in its state
+    const label = getCurrentWindow().label;
+    const unlisten = listen<{ source: string | null }>('drafts:changed', ({ payload }) => {
+      if (payload.source !== label) callback();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  },
+
   window: {
     minimize: () => getCurrentWindow().minimize(),
     maximize: () => getCurrentWindow().toggleMaximize(),
@@ -25,6 +25,7 @@ export class UpdaterState {
 	private updateInfo = $state<UpdateInfo | null>(null);
 	private checkInterval: ReturnType<typeof setInterval> | null = null;
 	private unlistenProgress: UnlistenFn | null = null;
+	private unlistenTray: UnlistenFn | null = null;
 
 	newVersion = $derived(this.updateInfo?.new_version ?? null);
 	releaseNotes = $derived(this.updateInfo?.release_notes ?? null);
@@ -47,6 +48,12 @@ export class UpdaterState {
 			}>('update-progress', (event) => {
 				this.downloadProgress = event.payload.percent ?? 0;
 			});
+
+			// "Check for Updates…" in the tray menu
+			this.unlistenTray = await listen('tray:check-update', async () => {
+				await this.checkForUpdate();
+				if (this.hasUpdate) this.openDialog();
+			});
 		} catch (e) {
 			console.debug('Updater init failed:', e);
 		}
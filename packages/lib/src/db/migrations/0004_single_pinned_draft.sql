@@ -0,0 +1,6 @@
+-- Keep only the most recently modified pinned draft before enforcing a single pin
+UPDATE `draft` SET `pinned` = 0 WHERE `pinned` = 1 AND `id` NOT IN (
+	SELECT `id` FROM `draft` WHERE `pinned` = 1 ORDER BY `modified_at` DESC LIMIT 1
+);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `idx_draft_single_pin` ON `draft` (`pinned`) WHERE `pinned` = 1;
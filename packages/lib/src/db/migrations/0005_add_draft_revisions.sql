@@ -0,0 +1,17 @@
+-- Saved versions of draft content, newest stored in full and older ones as
+-- deltas against the next newer revision
+CREATE TABLE `draft_revision` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`draft_uuid` text NOT NULL,
+	`created_at` text NOT NULL,
+	`updated_at` text NOT NULL,
+	`size` integer NOT NULL,
+	`content` text,
+	`delta` text
+);
+--> statement-breakpoint
+CREATE INDEX `idx_draft_revision_draft_uuid` ON `draft_revision` (`draft_uuid`, `id`);
+--> statement-breakpoint
+CREATE TRIGGER `draft_revision_cleanup` AFTER DELETE ON `draft` BEGIN
+	DELETE FROM `draft_revision` WHERE `draft_uuid` = old.`uuid`;
+END;
@@ -0,0 +1,2 @@
+ALTER TABLE `settings` ADD `silent_capture_shortcut` text;--> statement-breakpoint
+ALTER TABLE `settings` ADD `inbox_draft_uuid` text;
@@ -0,0 +1,2 @@
+ALTER TABLE `settings` ADD `text_expansion_enabled` integer DEFAULT false NOT NULL;--> statement-breakpoint
+ALTER TABLE `settings` ADD `text_expansion_exclusions` text;
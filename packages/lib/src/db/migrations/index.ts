@@ -9,6 +9,14 @@ import migration0001 from './0001_gigantic_sharon_ventura.sql?raw';
 import migration0002 from './0002_add_settings_table.sql?raw';
 // @ts-ignore - Vite handles .sql?raw imports at build time
 import migration0003 from './0003_add_automerge_tables.sql?raw';
+// @ts-ignore - Vite handles .sql?raw imports at build time
+import migration0004 from './0004_single_pinned_draft.sql?raw';
+// @ts-ignore - Vite handles .sql?raw imports at build time
+import migration0005 from './0005_add_draft_revisions.sql?raw';
+// @ts-ignore - Vite handles .sql?raw imports at build time
+import migration0006 from './0006_add_silent_capture_settings.sql?raw';
+// @ts-ignore - Vite handles .sql?raw imports at build time
+import migration0007 from './0007_add_text_expansion_settings.sql?raw';
 import journal from './meta/_journal.json';
 
 export interface Migration {
@@ -18,7 +26,7 @@ export interface Migration {
 }
 
 export const migrations: Migration[] = journal.entries.map((entry: any, i: number) => {
-	const migrationFiles = [migration0000, migration0001, migration0002, migration0003];
+	const migrationFiles = [migration0000, migration0001, migration0002, migration0003, migration0004, migration0005, migration0006, migration0007];
 	const sql = migrationFiles[i]
 		.split('--> statement-breakpoint')
 		.map((s: string) => s.trim())
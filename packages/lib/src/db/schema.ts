@@ -1,4 +1,5 @@
-import { sqliteTable, integer, text, index, blob, primaryKey } from 'drizzle-orm/sqlite-core';
+import { sql } from 'drizzle-orm';
+import { sqliteTable, integer, text, index, uniqueIndex, blob, primaryKey } from 'drizzle-orm/sqlite-core';
 
 export const drafts = sqliteTable('draft', {
   id: integer('id').primaryKey({ autoIncrement: true }),
@@ -15,14 +16,27 @@ export const drafts = sqliteTable('draft', {
   index('idx_draft_deleted_at').on(table.deletedAt),
   index('idx_draft_archived').on(table.archived),
   index('idx_draft_pinned').on(table.pinned),
+  // At most one pinned draft
+  uniqueIndex('idx_draft_single_pin').on(table.pinned).where(sql`${table.pinned} = 1`),
 ]);
 
+// Desktop also has `draft_fts`, an FTS5 index over `draft.content` kept in sync
+// by triggers. It's a desktop-only migration in src-tauri/migrations, since the
+// web app's sql.js build has no FTS5.
+
 export type Draft = typeof drafts.$inferSelect;
 export type NewDraft = typeof drafts.$inferInsert;
 
 export const settings = sqliteTable('settings', {
   id: integer('id').primaryKey().$default(() => 1),
   captureShortcut: text('capture_shortcut'),
+  // Unset when silent capture is off
+  silentCaptureShortcut: text('silent_capture_shortcut'),
+  // Draft silent captures append to; unset means the pinned draft
+  inboxDraftUuid: text('inbox_draft_uuid'),
+  textExpansionEnabled: integer('text_expansion_enabled', { mode: 'boolean' }).default(false).notNull(),
+  // Comma-separated window classes where snippets aren't expanded
+  textExpansionExclusions: text('text_expansion_exclusions'),
   createdAt: text('created_at').notNull(),
   updatedAt: text('updated_at').notNull(),
 });
@@ -30,6 +44,22 @@ export const settings = sqliteTable('settings', {
 export type Settings = typeof settings.$inferSelect;
 export type NewSettings = typeof settings.$inferInsert;
 
+// Newest revision holds `content`; older ones hold a `delta` against the next newer one
+export const draftRevisions = sqliteTable('draft_revision', {
+  id: integer('id').primaryKey({ autoIncrement: true }),
+  draftUuid: text('draft_uuid').notNull(),
+  createdAt: text('created_at').notNull(),
+  updatedAt: text('updated_at').notNull(),
+  size: integer('size').notNull(),
+  content: text('content'),
+  delta: text('delta'),
+}, (table) => [
+  index('idx_draft_revision_draft_uuid').on(table.draftUuid, table.id),
+]);
+
+export type DraftRevision = typeof draftRevisions.$inferSelect;
+export type NewDraftRevision = typeof draftRevisions.$inferInsert;
+
 // Automerge sync tables
 
 export const automergeChunk = sqliteTable('automerge_chunk', {
@@ -12,6 +12,9 @@ export interface PlatformCapabilities {
   /** Subscribe to app focus changes, returns cleanup function */
   onFocusChange(callback: () => void): () => void;
 
+  /** Subscribe to drafts changed elsewhere (other windows, CLI, tray), returns cleanup function */
+  onDraftsChanged?(callback: () => void): () => void;
+
   /** Window control capabilities (null if not supported) */
   window: {
     minimize(): Promise<void>;